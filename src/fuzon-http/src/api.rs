@@ -1,14 +1,52 @@
 extern crate apistos_schemars as schemars;
-use actix_web::{
-    web::{Data, Json, Query},
-    Result,
-};
+use crate::error::ApiError;
+use actix_web::web::{block, Data, Json, Path, Query};
 use apistos::{api_operation, ApiComponent};
-use fuzon::TermMatcher;
+use fuzon::{
+    Concept, FieldWeights, Scorer, ScorerKind, SearchOptions, SynonymScope, TermMatcher, TypeFilter,
+};
+use rayon::prelude::*;
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc};
+
+/// Options of code matching, shared by single and batch requests.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, ApiComponent)]
+pub struct MatchOptions {
+    /// Only match labels in this language (e.g. "en"). Labels without language tag are kept.
+    lang: Option<String>,
+    /// Only match synonyms with these scopes, comma-separated (e.g. "exact,narrow"). Other labels are kept.
+    scope: Option<String>,
+    /// Only match codes under this code (included), as an IRI or CURIE (e.g. "UBERON:0000061").
+    /// The collection must be loaded with its hierarchy.
+    root: Option<String>,
+    /// Only match codes of these kinds (class, property, individual) or rdf:types (IRIs or CURIEs), comma-separated.
+    /// The collection must be loaded with its types.
+    #[serde(rename = "type")]
+    types: Option<String>,
+    /// Also match deprecated codes, along with their replacements (default: false).
+    #[serde(default, deserialize_with = "lenient_option")]
+    include_deprecated: Option<bool>,
+    /// Group labels by code and return one match per code (default: false).
+    #[serde(default, deserialize_with = "lenient_option")]
+    concepts: Option<bool>,
+    /// Minimum similarity score (0 to 1) of returned matches.
+    #[serde(default, deserialize_with = "lenient_option")]
+    min_score: Option<f64>,
+    /// Rank codes by their labels, synonyms and definition to also match words of definitions,
+    /// with optional weights (e.g. "label=1,synonyms=0.7,definition=0.5", the defaults, or "default").
+    fields: Option<String>,
+    /// Scorer used to match labels: "fzy" (default), "jaro-winkler", "levenshtein",
+    /// "damerau-levenshtein" or "token-set".
+    scorer: Option<String>,
+    /// Maximum number of edits between the query and labels matched as typos with fzy
+    /// (default: 0, 1 or 2 by query length, 0 disables).
+    #[serde(default, deserialize_with = "lenient_option")]
+    typos: Option<usize>,
+    /// Return codes as CURIEs (e.g. "CL:0000738") when a prefix applies (default: false).
+    #[serde(default, deserialize_with = "lenient_option")]
+    curie: Option<bool>,
+}
 
 /// Request for codes similar to an input text query.
 #[derive(Debug, Deserialize, JsonSchema, ApiComponent)]
@@ -18,7 +56,54 @@ pub struct CodeRequest {
     /// Target collection on which to perform matching.
     collection: String,
     /// Number of top matches to return.
+    #[serde(deserialize_with = "lenient")]
     num: usize,
+    /// Options of matching.
+    #[serde(flatten)]
+    options: MatchOptions,
+}
+
+/// Request for codes similar to each of multiple text queries.
+#[derive(Debug, Deserialize, JsonSchema, ApiComponent)]
+pub struct BatchRequest {
+    /// Input queries, results are returned in the same order.
+    queries: Vec<BatchQuery>,
+    /// Default target collection for queries which do not specify one.
+    collection: Option<String>,
+    /// Default number of top matches to return per query.
+    num: usize,
+    /// Options applied to all queries.
+    #[serde(flatten)]
+    options: MatchOptions,
+}
+
+/// A single query within a batch request.
+#[derive(Debug, Deserialize, JsonSchema, ApiComponent)]
+pub struct BatchQuery {
+    /// Input free text query.
+    query: String,
+    /// Target collection, overrides the batch default.
+    collection: Option<String>,
+    /// Number of top matches to return, overrides the batch default.
+    num: Option<usize>,
+}
+
+/// Request for the ancestors of a code.
+#[derive(Debug, Deserialize, JsonSchema, ApiComponent)]
+pub struct AncestorRequest {
+    /// Collection containing the code.
+    collection: String,
+    /// Return codes as CURIEs (e.g. "CL:0000738") when a prefix applies (default: false).
+    curie: Option<bool>,
+}
+
+/// Request for a code by its identifier.
+#[derive(Debug, Deserialize, JsonSchema, ApiComponent)]
+pub struct LookupRequest {
+    /// IRI or CURIE of the code (e.g. "CL:0000738").
+    uri: String,
+    /// Return codes as CURIEs (e.g. "CL:0000738") when a prefix applies (default: false).
+    curie: Option<bool>,
 }
 
 /// Response model containing a single matched code.
@@ -28,8 +113,63 @@ pub struct CodeMatch {
     label: String,
     /// Unique identifier for the code.
     uri: String,
+    /// Language tag of the label, if any.
+    lang: Option<String>,
+    /// Scope of the label if it is a synonym (EXACT, BROAD, NARROW or RELATED).
+    synonym_scope: Option<String>,
+    /// Label which matched the query, when grouping by code.
+    matched_label: Option<String>,
+    /// Alternative labels of the code, when grouping by code.
+    alt_labels: Vec<String>,
+    /// Definition of the code (e.g. rdfs:comment or IAO:0000115), to tell apart similar labels.
+    description: Option<String>,
+    /// Whether the code is deprecated (only matched if requested).
+    deprecated: bool,
+    /// Codes replacing the code, if deprecated.
+    replaced_by: Vec<String>,
     /// Similarity score (0 to 1, higher is better).
-    score: Option<f64>,
+    score: f64,
+}
+
+/// A code along with its primary label.
+#[derive(Debug, Serialize, JsonSchema, ApiComponent)]
+pub struct Code {
+    /// Unique identifier for the code.
+    uri: String,
+    /// Human-readable label of the code, or its identifier if it has none.
+    label: String,
+}
+
+/// Response model containing the ancestors of a code.
+#[derive(Debug, Serialize, JsonSchema, ApiComponent)]
+pub struct AncestorResponse {
+    /// Unique identifier of the code.
+    uri: String,
+    /// Direct parents of the code.
+    parents: Vec<Code>,
+    /// All ancestors of the code, nearest first.
+    ancestors: Vec<Code>,
+}
+
+/// Response model describing a known code.
+#[derive(Debug, Serialize, JsonSchema, ApiComponent)]
+pub struct LookupResponse {
+    /// Unique identifier of the code.
+    uri: String,
+    /// Primary label of the code.
+    label: String,
+    /// Alternative labels of the code.
+    alt_labels: Vec<String>,
+    /// Definition of the code, if any.
+    description: Option<String>,
+    /// Whether the code is deprecated.
+    deprecated: bool,
+    /// Codes replacing the code, if deprecated.
+    replaced_by: Vec<String>,
+    /// rdf:types of the code, if the collection was loaded with its types.
+    types: Vec<String>,
+    /// Direct parents of the code, if the collection was loaded with its hierarchy.
+    parents: Vec<Code>,
 }
 
 /// Response model containing a list of matched codes.
@@ -39,17 +179,316 @@ pub struct MatchResponse {
     codes: Vec<CodeMatch>,
 }
 
+/// Response model containing matched codes for each query of a batch.
+#[derive(Debug, Serialize, JsonSchema, ApiComponent)]
+pub struct BatchResponse {
+    /// Matches of each query, in the order of input queries.
+    results: Vec<MatchResponse>,
+}
+
 /// Response model containing the names of available collections.
 #[derive(Debug, Serialize, JsonSchema, ApiComponent)]
 pub struct CollectionList {
     /// Names of available collections.
     collections: Vec<String>,
+    /// Collections which failed to load.
+    unavailable: Vec<UnavailableCollection>,
+}
+
+/// A collection which could not be loaded.
+#[derive(Debug, Serialize, JsonSchema, ApiComponent)]
+pub struct UnavailableCollection {
+    /// Name of the collection.
+    name: String,
+    /// Reason why the collection could not be loaded.
+    reason: String,
+}
+
+/// Maximum number of matches returned per query.
+pub const MAX_NUM: usize = 1000;
+
+/// Maximum number of queries in a batch request.
+pub const MAX_BATCH: usize = 10000;
+
+/// A value given as is (JSON), or as a string (query parameters). Fields of flattened
+/// structs are deserialized from query strings as strings only, and parsed from them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Lenient<T> {
+    Value(T),
+    Text(String),
+}
+
+impl<T: FromStr> Lenient<T>
+where
+    T::Err: fmt::Display,
+{
+    fn parse<E: de::Error>(self) -> Result<T, E> {
+        match self {
+            Lenient::Value(value) => Ok(value),
+            Lenient::Text(text) => text.parse().map_err(E::custom),
+        }
+    }
+}
+
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    Lenient::deserialize(deserializer)?.parse()
+}
+
+fn lenient_option<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    Option::<Lenient<T>>::deserialize(deserializer)?
+        .map(Lenient::parse)
+        .transpose()
+}
+
+/// A collection is either loaded, or unavailable because its sources failed to load.
+#[derive(Clone, Debug)]
+pub enum Collection {
+    Ready(TermMatcher),
+    Unavailable(String),
 }
 
 /// Shared app state built from config and used by services
 #[derive(Clone, Debug)]
 pub struct AppState {
-    pub collections: Arc<HashMap<String, TermMatcher>>,
+    pub collections: Arc<HashMap<String, Collection>>,
+}
+
+impl AppState {
+    /// Get the matcher of a loaded collection.
+    pub fn matcher(&self, name: &str) -> Result<&TermMatcher, ApiError> {
+        match self.collections.get(name) {
+            Some(Collection::Ready(matcher)) => Ok(matcher),
+            Some(Collection::Unavailable(_)) => {
+                Err(ApiError::CollectionUnavailable(name.to_string()))
+            }
+            None => Err(ApiError::CollectionNotFound(name.to_string())),
+        }
+    }
+}
+
+/// Identifier of a code in responses, as a CURIE if requested and a prefix applies.
+fn code_id(matcher: &TermMatcher, uri: &str, curie: bool) -> String {
+    if curie {
+        matcher.compact(uri)
+    } else {
+        uri.to_string()
+    }
+}
+
+impl MatchOptions {
+    /// Check that options are within bounds.
+    fn validate(&self) -> Result<(), ApiError> {
+        self.scopes()?;
+        self.type_filters()?;
+        self.field_weights()?;
+        self.scorer_kind()?;
+        if let Some(min_score) = self.min_score {
+            if !(0.0..=1.0).contains(&min_score) {
+                return Err(ApiError::InvalidRequest(
+                    "min_score must be between 0 and 1".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Synonym scopes to match, if restricted.
+    fn scopes(&self) -> Result<Option<Vec<SynonymScope>>, ApiError> {
+        self.scope
+            .as_ref()
+            .map(|scopes| {
+                scopes
+                    .split(',')
+                    .map(|s| s.trim().parse().map_err(ApiError::InvalidRequest))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    /// Kinds or types of codes to match, if restricted.
+    fn type_filters(&self) -> Result<Option<Vec<TypeFilter>>, ApiError> {
+        self.types
+            .as_ref()
+            .map(|types| {
+                types
+                    .split(',')
+                    .map(|t| t.trim().parse().map_err(ApiError::InvalidRequest))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    /// Weights of the fields to rank codes by, if ranking by fields.
+    fn field_weights(&self) -> Result<Option<FieldWeights>, ApiError> {
+        self.fields
+            .as_ref()
+            .map(|fields| fields.parse().map_err(ApiError::InvalidRequest))
+            .transpose()
+    }
+
+    /// Scorer used to match labels, fzy by default.
+    fn scorer_kind(&self) -> Result<ScorerKind, ApiError> {
+        self.scorer
+            .as_ref()
+            .map(|scorer| scorer.parse().map_err(ApiError::InvalidRequest))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Search options of the matcher, once validated.
+    fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            lang: self.lang.clone(),
+            // Scopes are checked when validating the request
+            scopes: self.scopes().unwrap_or_default(),
+            // CURIEs are expanded with the prefixes of the collection
+            roots: self.root.clone().map(|root| vec![root]),
+            // Types are checked when validating the request
+            types: self.type_filters().unwrap_or_default(),
+            include_deprecated: self.include_deprecated.unwrap_or(false),
+            // Weights are checked when validating the request
+            fields: self.field_weights().unwrap_or_default(),
+            // The scorer is checked when validating the request
+            scorer: self.scorer_kind().unwrap_or_default(),
+            typos: self.typos,
+        }
+    }
+}
+
+impl CodeRequest {
+    /// Check that parameters are within bounds.
+    fn validate(&self) -> Result<(), ApiError> {
+        if self.num == 0 || self.num > MAX_NUM {
+            return Err(ApiError::InvalidRequest(format!(
+                "num must be between 1 and {}",
+                MAX_NUM
+            )));
+        }
+
+        self.options.validate()
+    }
+
+    /// Check that the target matcher can apply the request filters.
+    fn validate_for(&self, matcher: &TermMatcher) -> Result<(), ApiError> {
+        if self.options.root.is_some() && matcher.hierarchy().is_empty() {
+            return Err(ApiError::InvalidRequest(format!(
+                "root requires collection {} to be loaded with its hierarchy",
+                self.collection
+            )));
+        }
+        if self.options.types.is_some() && matcher.types().is_empty() {
+            return Err(ApiError::InvalidRequest(format!(
+                "type requires collection {} to be loaded with its types",
+                self.collection
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Top matching codes for the query in the input matcher.
+    fn find_codes(&self, matcher: &TermMatcher) -> Vec<CodeMatch> {
+        let options = self.options.search_options();
+        let deprecations = matcher.deprecations();
+        let description = |uri: &str| matcher.definitions().get(uri).map(String::from);
+        // Normalizing keeps the order of raw scores, so matches reaching
+        // min_score come first among the top num
+        let min_score = self.options.min_score.unwrap_or(0.0);
+        // Scores are relative to the query as matched
+        let query = matcher.normalizer().normalize(&self.query);
+        let curie = self.options.curie.unwrap_or(false);
+        // Scores of fields are already between 0 and 1
+        let scale = |score: f64| match options.fields {
+            Some(_) => score,
+            None => options.scorer.normalize(score, &query),
+        };
+        let code = |uri: &str| code_id(matcher, uri, curie);
+        let replaced_by = |uri: &str| {
+            deprecations
+                .replaced_by(uri)
+                .iter()
+                .map(|r| code(r))
+                .collect()
+        };
+
+        if self.options.concepts.unwrap_or(false) {
+            matcher
+                .top_concepts_with(&self.query, self.num, &options)
+                .into_iter()
+                .map(|m| CodeMatch {
+                    label: m.concept.label,
+                    uri: code(&m.concept.uri),
+                    lang: None,
+                    synonym_scope: None,
+                    matched_label: Some(m.matched_label),
+                    alt_labels: m.concept.alt_labels,
+                    description: description(&m.concept.uri),
+                    deprecated: deprecations.is_deprecated(&m.concept.uri),
+                    replaced_by: replaced_by(&m.concept.uri),
+                    score: scale(m.score),
+                })
+                .filter(|c| c.score >= min_score)
+                .collect()
+        } else {
+            matcher
+                .rank_top_terms(&self.query, self.num, &options)
+                .into_iter()
+                .map(|(t, score)| CodeMatch {
+                    label: t.label.clone(),
+                    uri: code(&t.uri),
+                    lang: t.lang.clone(),
+                    synonym_scope: t.synonym_scope().map(|s| s.to_string()),
+                    matched_label: None,
+                    alt_labels: Vec::new(),
+                    description: description(&t.uri),
+                    deprecated: deprecations.is_deprecated(&t.uri),
+                    replaced_by: replaced_by(&t.uri),
+                    score: scale(score),
+                })
+                .filter(|c| c.score >= min_score)
+                .collect()
+        }
+    }
+}
+
+impl BatchRequest {
+    /// Expand the batch into individual requests, applying batch defaults.
+    fn into_requests(self) -> Result<Vec<CodeRequest>, ApiError> {
+        if self.queries.len() > MAX_BATCH {
+            return Err(ApiError::InvalidRequest(format!(
+                "at most {} queries are allowed per batch",
+                MAX_BATCH
+            )));
+        }
+        self.queries
+            .into_iter()
+            .map(|q| {
+                let collection = q.collection.or(self.collection.clone()).ok_or_else(|| {
+                    ApiError::InvalidRequest(format!("no collection for query: {}", q.query))
+                })?;
+                let req = CodeRequest {
+                    query: q.query,
+                    collection,
+                    num: q.num.unwrap_or(self.num),
+                    options: self.options.clone(),
+                };
+                req.validate()?;
+                Ok(req)
+            })
+            .collect()
+    }
 }
 
 #[api_operation(
@@ -57,13 +496,26 @@ pub struct AppState {
     summary = "List available collections",
     description = "Returns the names of available collections."
 )]
-pub(crate) async fn list_collections(data: Data<AppState>) -> Result<Json<CollectionList>> {
-    let collections = CollectionList {
-        collections: data.collections.keys().cloned().collect()
+pub(crate) async fn list_collections(
+    data: Data<AppState>,
+) -> Result<Json<CollectionList>, ApiError> {
+    let mut collections = CollectionList {
+        collections: Vec::new(),
+        unavailable: Vec::new(),
     };
+    for (name, collection) in data.collections.iter() {
+        match collection {
+            Collection::Ready(_) => collections.collections.push(name.clone()),
+            Collection::Unavailable(reason) => {
+                collections.unavailable.push(UnavailableCollection {
+                    name: name.clone(),
+                    reason: reason.clone(),
+                })
+            }
+        }
+    }
 
     Ok(Json(collections))
-
 }
 
 // Top matching codes from collection for query: /top?collection={collection}&query={foobar}&num={10}
@@ -71,20 +523,140 @@ pub(crate) async fn list_collections(data: Data<AppState>) -> Result<Json<Collec
     tag = "codes",
     summary = "Top N codes.",
     description = r###"Fuzzy matches the input query against the description of codes in target collection.
-    The top N closest matches are returned."###,
+    The top N closest matches are returned."###
+)]
+pub(crate) async fn top_codes(
+    data: Data<AppState>,
+    req: Query<CodeRequest>,
+) -> Result<Json<MatchResponse>, ApiError> {
+    let req = req.into_inner();
+    req.validate()?;
+    req.validate_for(data.matcher(&req.collection)?)?;
+
+    // Ranking is CPU-bound, keep it off the async workers
+    let codes = block(move || {
+        data.matcher(&req.collection)
+            .map(|matcher| req.find_codes(matcher))
+    })
+    .await
+    .map_err(|err| ApiError::Internal(err.to_string()))??;
+
+    Ok(Json(MatchResponse { codes }))
+}
+
+// Top matching codes for each query of a batch: POST /batch
+#[api_operation(
+    tag = "codes",
+    summary = "Top N codes for a batch of queries.",
+    description = r###"Fuzzy matches each input query against the description of codes in its target collection.
+    Queries are matched in parallel and the top N closest matches of each query are returned in input order."###
+)]
+pub(crate) async fn batch_codes(
+    data: Data<AppState>,
+    req: Json<BatchRequest>,
+) -> Result<Json<BatchResponse>, ApiError> {
+    let requests = req.into_inner().into_requests()?;
+    // Fail early if any collection cannot be queried
+    for req in &requests {
+        req.validate_for(data.matcher(&req.collection)?)?;
+    }
+
+    let results = block(move || {
+        requests
+            .par_iter()
+            .map(|req| {
+                data.matcher(&req.collection).map(|matcher| MatchResponse {
+                    codes: req.find_codes(matcher),
+                })
+            })
+            .collect::<Result<Vec<MatchResponse>, ApiError>>()
+    })
+    .await
+    .map_err(|err| ApiError::Internal(err.to_string()))??;
+
+    Ok(Json(BatchResponse { results }))
+}
+
+// Ancestors of a code in a collection: /codes/{uri}/ancestors?collection={collection}
+#[api_operation(
+    tag = "codes",
+    summary = "Ancestors of a code.",
+    description = r###"Returns the parents and all ancestors of a code (rdfs:subClassOf, skos:broader),
+    if the collection was loaded with its hierarchy. The code IRI (or CURIE) must be percent-encoded."###
+)]
+pub(crate) async fn ancestor_codes(
+    data: Data<AppState>,
+    uri: Path<String>,
+    req: Query<AncestorRequest>,
+) -> Result<Json<AncestorResponse>, ApiError> {
+    let matcher = data.matcher(&req.collection)?;
+    // Codes may be given as IRIs or CURIEs, but are identified by IRIs
+    let uri = matcher.term_uri(&uri);
+    if matcher.lookup_terms(&uri).is_empty() && matcher.hierarchy().parents(&uri).is_empty() {
+        return Err(ApiError::CodeNotFound(uri));
+    }
+    let curie = req.curie.unwrap_or(false);
+    let codes = |concepts: Vec<Concept>| {
+        concepts
+            .into_iter()
+            .map(|c| Code {
+                uri: code_id(matcher, &c.uri, curie),
+                label: c.label,
+            })
+            .collect()
+    };
 
+    Ok(Json(AncestorResponse {
+        parents: codes(matcher.parents(&uri)),
+        ancestors: codes(matcher.ancestors(&uri)),
+        uri: code_id(matcher, &uri, curie),
+    }))
+}
+
+// Labels and metadata of a code in a collection: /codes/{collection}/lookup?uri={uri}
+#[api_operation(
+    tag = "codes",
+    summary = "Look up a code.",
+    description = r###"Returns the labels of a code given as an IRI or CURIE, along with its definition,
+    deprecation, types and parents when available. Unknown codes are not found (404), which can be used to validate codes."###
 )]
-pub(crate) async fn top_codes(data: Data<AppState>, req: Query<CodeRequest>) -> Result<Json<MatchResponse>> {
-
-    let top_terms: Vec<CodeMatch> = data.collections
-        .get(&req.collection)
-        .expect(&format!("Collection not found: {}", req.collection))
-        .top_terms(&req.query, req.num)
-        .into_iter()
-        .map(|t| CodeMatch {
-            label: t.label.clone(), uri: t.uri.clone(), score: None
-        })
-        .collect();
-
-    Ok(Json(MatchResponse{ codes: top_terms }))
+pub(crate) async fn lookup_code(
+    data: Data<AppState>,
+    collection: Path<String>,
+    req: Query<LookupRequest>,
+) -> Result<Json<LookupResponse>, ApiError> {
+    let matcher = data.matcher(&collection)?;
+    let Some(concept) = matcher.lookup(&req.uri) else {
+        return Err(ApiError::CodeNotFound(req.uri.clone()));
+    };
+    let curie = req.curie.unwrap_or(false);
+    let code = |uri: &str| code_id(matcher, uri, curie);
+    let deprecations = matcher.deprecations();
+
+    Ok(Json(LookupResponse {
+        uri: code(&concept.uri),
+        label: concept.label,
+        alt_labels: concept.alt_labels,
+        description: matcher.definitions().get(&concept.uri).map(String::from),
+        deprecated: deprecations.is_deprecated(&concept.uri),
+        replaced_by: deprecations
+            .replaced_by(&concept.uri)
+            .iter()
+            .map(|r| code(r))
+            .collect(),
+        types: matcher
+            .types()
+            .types(&concept.uri)
+            .iter()
+            .map(|t| code(t))
+            .collect(),
+        parents: matcher
+            .parents(&concept.uri)
+            .into_iter()
+            .map(|c| Code {
+                uri: code(&c.uri),
+                label: c.label,
+            })
+            .collect(),
+    }))
 }
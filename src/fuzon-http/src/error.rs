@@ -0,0 +1,76 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use apistos::ApiErrorComponent;
+use serde::Serialize;
+use std::fmt;
+
+/// Errors returned by the API, rendered as JSON problem details (RFC 9457).
+// clippy mistakes repeated `status` entries for duplicated attributes
+#[allow(clippy::duplicated_attributes)]
+#[derive(Debug, Clone, ApiErrorComponent)]
+#[openapi_error(
+    status(code = 400, description = "Invalid request parameters."),
+    status(code = 404, description = "Collection or code not found."),
+    status(code = 500, description = "Internal server error."),
+    status(
+        code = 503,
+        description = "Collection failed to load and is unavailable."
+    )
+)]
+pub enum ApiError {
+    /// The request parameters are invalid.
+    InvalidRequest(String),
+    /// The requested collection does not exist.
+    CollectionNotFound(String),
+    /// The requested collection exists but could not be loaded.
+    CollectionUnavailable(String),
+    /// The requested code does not exist in the collection.
+    CodeNotFound(String),
+    /// The request could not be processed.
+    Internal(String),
+}
+
+/// Problem details body of error responses.
+#[derive(Debug, Serialize)]
+struct Problem {
+    title: String,
+    status: u16,
+    detail: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
+            ApiError::CollectionNotFound(name) => write!(f, "Collection not found: {}", name),
+            ApiError::CollectionUnavailable(name) => {
+                write!(f, "Collection unavailable: {}", name)
+            }
+            ApiError::CodeNotFound(uri) => write!(f, "Code not found: {}", uri),
+            ApiError::Internal(msg) => write!(f, "Internal error: {}", msg),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::CollectionNotFound(_) | ApiError::CodeNotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::CollectionUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let problem = Problem {
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: self.to_string(),
+        };
+
+        HttpResponse::build(status)
+            .content_type("application/problem+json")
+            .json(problem)
+    }
+}
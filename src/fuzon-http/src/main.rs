@@ -1,60 +1,232 @@
-use crate::api::{AppState, list_collections, top_codes};
-use std::collections::HashMap;
+use crate::{
+    api::{
+        ancestor_codes, batch_codes, list_collections, lookup_code, top_codes, AppState, Collection,
+    },
+    error::ApiError,
+};
 use actix_web::{
     middleware::Logger,
-    web::{block, Data},
-    App,
-    HttpServer,
+    web::{block, Data, JsonConfig, QueryConfig},
+    App, HttpServer,
 };
-use apistos::app::{BuildConfig, OpenApiWrapper};
-use apistos::info::Info;
-use apistos::spec::Spec;
-use apistos::web::{
-    get,
-    resource,
-    scope,
+use apistos::{
+    app::{BuildConfig, OpenApiWrapper},
+    info::Info,
+    spec::Spec,
+    web::{get, post, resource, scope, ServiceConfig},
+    ScalarConfig,
 };
-use apistos::ScalarConfig;
 use clap::Parser;
-use fuzon::TermMatcher;
-use log::info;
+use fuzon::{
+    get_reader, parse_source_format, table::TableMapping, PrefixMap, TermMatcher,
+    TermMatcherBuilder,
+};
+use log::{error, info, warn};
 use serde::Deserialize;
-use serde_json;
-use std::env;
-use std::sync::Arc;
-use std::fs::File;
+use std::{collections::HashMap, env, fs::File, sync::Arc};
 
 mod api;
+mod error;
 
 // Config file structure
-# [derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 struct Config {
     host: String,
     port: u16,
-    collections: HashMap<String, Vec<String>>,
+    collections: HashMap<String, CollectionConfig>,
+}
+
+// A collection is either a list of sources, or sources with extra settings.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum CollectionConfig {
+    Sources(Vec<SourceConfig>),
+    Detailed {
+        sources: Vec<SourceConfig>,
+        #[serde(default)]
+        predicates: PredicateConfig,
+        #[serde(default)]
+        lenient: bool,
+        // Also gather oboInOwl synonyms
+        #[serde(default)]
+        synonyms: bool,
+        // Columns of tabular sources, boxed to keep the enum small
+        #[serde(default)]
+        table: Box<TableMapping>,
+        // Also gather parent classes, for the ancestors endpoint
+        #[serde(default)]
+        hierarchy: bool,
+        // Also gather rdf:type, for type filters
+        #[serde(default)]
+        types: bool,
+        // JSON-LD context or prefix map (path or URL) used for CURIEs
+        prefixes: Option<String>,
+        // Predicates of definitions by priority, replacing the defaults
+        definitions: Option<Vec<String>>,
+        // Normalization steps of labels and queries, e.g. "default,greek"
+        normalize: Option<String>,
+    },
+}
+
+// A source is either a path/URL, or a path/URL with an explicit RDF format.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum SourceConfig {
+    Path(String),
+    Detailed {
+        path: String,
+        format: Option<String>,
+    },
+}
+
+impl SourceConfig {
+    fn path(&self) -> &str {
+        match self {
+            SourceConfig::Path(path) => path,
+            SourceConfig::Detailed { path, .. } => path,
+        }
+    }
+}
+
+// Changes to the default annotation predicates used to gather labels.
+#[derive(Clone, Debug, Default, Deserialize)]
+struct PredicateConfig {
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+    replace: Option<Vec<String>>,
 }
 
+impl CollectionConfig {
+    fn source_configs(&self) -> &[SourceConfig] {
+        match self {
+            CollectionConfig::Sources(sources) => sources,
+            CollectionConfig::Detailed { sources, .. } => sources,
+        }
+    }
+
+    fn sources(&self) -> Vec<&str> {
+        self.source_configs().iter().map(|s| s.path()).collect()
+    }
+
+    // Fails if a source has an unknown format.
+    fn builder(&self) -> Result<TermMatcherBuilder, String> {
+        let mut builder = TermMatcher::builder();
+        for source in self.source_configs() {
+            if let SourceConfig::Detailed {
+                path,
+                format: Some(format),
+            } = source
+            {
+                let source_format = parse_source_format(format)
+                    .ok_or_else(|| format!("{}: unknown format: {}", path, format))?;
+                builder = builder.with_source_format(path, source_format);
+            }
+        }
+        if let CollectionConfig::Detailed {
+            predicates,
+            lenient,
+            synonyms,
+            table,
+            hierarchy,
+            types,
+            prefixes,
+            definitions,
+            normalize,
+            ..
+        } = self
+        {
+            builder = builder
+                .lenient(*lenient)
+                .hierarchy(*hierarchy)
+                .types(*types)
+                .with_table_mapping(table.as_ref().clone());
+            if let Some(replace) = &predicates.replace {
+                builder = builder.with_predicates(replace);
+            }
+            if *synonyms {
+                builder = builder.with_synonyms();
+            }
+            for predicate in &predicates.add {
+                builder = builder.with_predicate(predicate);
+            }
+            for predicate in &predicates.remove {
+                builder = builder.without_predicate(predicate);
+            }
+            if let Some(definitions) = definitions {
+                builder = builder.with_definition_predicates(definitions);
+            }
+            if let Some(normalize) = normalize {
+                builder = builder.with_normalizer(normalize.parse()?);
+            }
+            if let Some(path) = prefixes {
+                let reader = get_reader(path).map_err(|e| format!("{}: {:#}", path, e))?;
+                let prefixes =
+                    PrefixMap::from_reader(reader).map_err(|e| format!("{}: {}", path, e))?;
+                builder = builder.with_prefixes(prefixes);
+            }
+        }
+
+        Ok(builder)
+    }
+}
 
 impl AppState {
     fn from_config(data: Config) -> Self {
-        let collections = data.clone()
+        let collections = data
+            .clone()
             .collections
             .into_iter()
             .inspect(|(k, _)| info!("Loading collection: {}...", k))
-            .map(|(k, v)| (
-                k, 
-                TermMatcher::from_paths(
-                    v.iter().map(|s| &**s).collect()).unwrap()
-                )
-            )
+            .map(|(k, v)| {
+                // Failed collections are kept as unavailable instead of aborting startup
+                let loaded = v.builder().and_then(|b| {
+                    b.build_with_report(v.sources())
+                        .map_err(|e| format!("{:#}", e))
+                });
+                let collection = match loaded {
+                    Ok((matcher, reports)) => {
+                        for report in reports.iter().filter(|r| r.errors > 0) {
+                            warn!("Collection {}: {}", k, report);
+                        }
+                        Collection::Ready(matcher)
+                    }
+                    Err(err) => {
+                        error!("Failed to load collection {}: {}", k, err);
+                        Collection::Unavailable(err)
+                    }
+                };
+                (k, collection)
+            })
             .collect();
 
         info!("Initialized with: {:?}", &data);
-        AppState { collections: Arc::new(collections) }
+        AppState {
+            collections: Arc::new(collections),
+        }
     }
 }
 
-
+// Routes of the api, along with the handlers of invalid parameters.
+fn routes(cfg: &mut ServiceConfig) {
+    cfg.app_data(
+        QueryConfig::default()
+            .error_handler(|err, _| ApiError::InvalidRequest(err.to_string()).into()),
+    )
+    .app_data(
+        JsonConfig::default()
+            .error_handler(|err, _| ApiError::InvalidRequest(err.to_string()).into()),
+    )
+    .service(resource("/collections").route(get().to(list_collections)))
+    .service(
+        scope("/codes")
+            .service(resource("/top").route(get().to(top_codes)))
+            .service(resource("/batch").route(post().to(batch_codes)))
+            .service(resource("/{uri}/ancestors").route(get().to(ancestor_codes)))
+            .service(resource("/{collection}/lookup").route(get().to(lookup_code))),
+    );
+}
 
 /// http server to serve the fuzon terminology matching api
 #[derive(Parser, Debug)]
@@ -65,23 +237,23 @@ struct Args {
     config: String,
 }
 
-
 #[actix_web::main] // or #[tokio::main]
 async fn main() -> std::io::Result<()> {
-    env::set_var("RUST_LOG", "fuzon_http=info,actix_web=warn,actix_server=info");
+    env::set_var(
+        "RUST_LOG",
+        "fuzon_http=info,actix_web=warn,actix_server=info",
+    );
     env_logger::init();
     let args = Args::parse();
     let config_path = args.config;
 
-    let config: Config = serde_json::from_reader(
-        File::open(config_path).expect("Failed to open config file.")
-    ).expect("Failed to parse config.");
+    let config: Config =
+        serde_json::from_reader(File::open(config_path).expect("Failed to open config file."))
+            .expect("Failed to parse config.");
     let host = config.host.clone();
     let port = config.port as u16;
 
-    let data = block(move || 
-        AppState::from_config(config)
-    )
+    let data = block(move || AppState::from_config(config))
         .await
         .expect("Failed to initialize state from config.");
 
@@ -100,18 +272,222 @@ async fn main() -> std::io::Result<()> {
             .document(spec)
             .wrap(Logger::default())
             .app_data(Data::new(data.clone()))
-            .service(resource("/collections").route(get().to(list_collections)))
-            .service(
-                scope("/codes")
-                    .service(resource("/top").route(get().to(top_codes)))
-            )
+            .configure(routes)
             .build_with(
                 "/openapi.json",
-                BuildConfig::default()
-                    .with(ScalarConfig::new(&"/")),
+                BuildConfig::default().with(ScalarConfig::new(&"/")),
             )
     })
     .bind((host, port))?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test};
+    use serde_json::{json, Value};
+
+    // A ready collection of schema.org terms, and one which failed to load
+    fn state() -> AppState {
+        let config: Config = serde_json::from_value(json!({
+            "host": "localhost",
+            "port": 8080,
+            "collections": {
+                "schema": ["../../data/test_schema.ttl"],
+                "missing": ["../../data/missing.ttl"]
+            }
+        }))
+        .unwrap();
+        AppState::from_config(config)
+    }
+
+    // Status, content type and JSON body of the response to a request
+    async fn call(req: test::TestRequest) -> (StatusCode, String, Value) {
+        let app = test::init_service(
+            App::new()
+                .document(Spec::default())
+                .app_data(Data::new(state()))
+                .configure(routes)
+                .build("/openapi.json"),
+        )
+        .await;
+        let resp = test::call_service(&app, req.to_request()).await;
+        let status = resp.status();
+        let content_type = resp
+            .headers()
+            .get("content-type")
+            .map(|v| v.to_str().unwrap().to_string())
+            .unwrap_or_default();
+        let body = test::read_body(resp).await;
+        (status, content_type, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn get_json(uri: &str) -> (StatusCode, String, Value) {
+        call(test::TestRequest::get().uri(uri)).await
+    }
+
+    async fn post_batch(batch: Value) -> (StatusCode, String, Value) {
+        call(
+            test::TestRequest::post()
+                .uri("/codes/batch")
+                .set_json(batch),
+        )
+        .await
+    }
+
+    fn scores(body: &Value) -> Vec<f64> {
+        body["codes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["score"].as_f64().unwrap())
+            .collect()
+    }
+
+    #[actix_web::test]
+    async fn top_scores_normalized() {
+        let (status, _, body) = get_json("/codes/top?collection=schema&query=person&num=5").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["codes"][0]["label"], "Person");
+        let scores = scores(&body);
+        assert_eq!(scores[0], 1.0);
+        assert!(scores.iter().all(|s| (0.0..=1.0).contains(s)));
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[actix_web::test]
+    async fn top_min_score() {
+        let (_, _, all) = get_json("/codes/top?collection=schema&query=o&num=100").await;
+        let min_score = scores(&all)[1];
+        let (status, _, body) = get_json(&format!(
+            "/codes/top?collection=schema&query=o&num=100&min_score={}",
+            min_score
+        ))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let kept = scores(&body);
+        assert!(kept.len() >= 2);
+        assert!(kept.iter().all(|s| *s >= min_score));
+        assert_eq!(
+            kept.len(),
+            scores(&all).iter().filter(|s| **s >= min_score).count()
+        );
+
+        // Truncation keeps the best qualifying matches
+        let (_, _, top) = get_json(&format!(
+            "/codes/top?collection=schema&query=o&num=1&min_score={}",
+            min_score
+        ))
+        .await;
+        assert_eq!(top["codes"][0], body["codes"][0]);
+        assert_eq!(scores(&top).len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn errors_as_problems() {
+        let cases = [
+            (
+                "/codes/top?collection=foo&query=bar&num=3",
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                "/codes/top?collection=missing&query=bar&num=3",
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                "/codes/top?collection=schema&query=bar&num=0",
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/codes/top?collection=schema&query=bar&num=3&min_score=2",
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/codes/top?collection=schema&query=bar",
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/codes/schema/lookup?uri=http://example.org/Foo",
+                StatusCode::NOT_FOUND,
+            ),
+        ];
+        for (uri, expected) in cases {
+            let (status, content_type, body) = get_json(uri).await;
+            assert_eq!(status, expected, "{}", uri);
+            assert_eq!(content_type, "application/problem+json");
+            assert_eq!(body["status"], expected.as_u16());
+            assert_eq!(body["title"], expected.canonical_reason().unwrap());
+        }
+        let (_, _, body) = get_json("/codes/top?collection=foo&query=bar&num=3").await;
+        assert_eq!(body["detail"], "Collection not found: foo");
+    }
+
+    #[actix_web::test]
+    async fn batch_in_input_order() {
+        let queries = ["person", "keywords", "organization", "abstract"];
+        let (status, _, body) = post_batch(json!({
+            "collection": "schema",
+            "num": 2,
+            "queries": queries.iter().map(|q| json!({"query": q})).collect::<Vec<_>>(),
+        }))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results.len(), queries.len());
+        for (query, result) in queries.iter().zip(results) {
+            // Each result is the same as a single query
+            let (_, _, single) = get_json(&format!(
+                "/codes/top?collection=schema&query={}&num=2",
+                query
+            ))
+            .await;
+            assert_eq!(result, &single);
+            assert_eq!(
+                result["codes"][0]["label"].as_str().unwrap().to_lowercase(),
+                *query
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn batch_options() {
+        let (status, _, body) = post_batch(json!({
+            "collection": "schema",
+            "num": 3,
+            "min_score": 0.5,
+            "concepts": true,
+            "queries": [{"query": "person", "num": 1}, {"query": "o"}],
+        }))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["results"][0]["codes"].as_array().unwrap().len(), 1);
+        assert_eq!(body["results"][0]["codes"][0]["matched_label"], "Person");
+        assert!(scores(&body["results"][1]).iter().all(|s| *s >= 0.5));
+
+        let cases = [
+            (
+                json!({"num": 3, "queries": [{"query": "person"}]}),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                json!({"collection": "schema", "num": 0, "queries": [{"query": "a"}]}),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                json!({"collection": "foo", "num": 3, "queries": [{"query": "a"}]}),
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                json!({"collection": "schema", "num": 3, "queries": [{"query": "a", "collection": "missing"}]}),
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+        ];
+        for (batch, expected) in cases {
+            let (status, content_type, _) = post_batch(batch).await;
+            assert_eq!(status, expected);
+            assert_eq!(content_type, "application/problem+json");
+        }
+    }
+}
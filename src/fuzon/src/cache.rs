@@ -7,7 +7,15 @@ use std::{
 use anyhow::Result;
 use reqwest::{blocking::Client, Url};
 
-use crate::TermMatcher;
+use crate::{
+    sparql::{is_sparql_source, SparqlSource},
+    TermMatcher, TermMatcherBuilder,
+};
+
+/// Version of the serialized `TermMatcher`, hashed into cache keys so that
+/// caches written with another layout are not loaded. Bump it whenever the
+/// serialized fields of `TermMatcher` or `Term` change.
+pub const CACHE_VERSION: u32 = 1;
 
 /// Requests headers with redirection to create a stamp for the URL
 /// consisting of the last modified date and/or ETag.
@@ -35,23 +43,39 @@ pub fn get_file_stamp(path: &str) -> Result<String> {
 
 /// Generate a fixed cache key based on a collection of source paths.
 /// Each path is converted to a stamp in the format "{path}-{fingerprint}-{modified-date}".
-/// Stamps are then concatenated and hash of this concatenation is returned.
-pub fn get_cache_key(paths: &mut Vec<&str>) -> Result<String> {
+/// Stamps are then concatenated and hash of this concatenation, along with
+/// `CACHE_VERSION`, is returned.
+pub fn get_cache_key(paths: &mut [&str]) -> Result<String> {
+    get_cache_key_with(paths, &TermMatcherBuilder::default())
+}
+
+/// Generate a fixed cache key based on a collection of source paths and the
+/// builder configuration used to gather their terms. The default configuration
+/// yields the same key as `get_cache_key`.
+pub fn get_cache_key_with(paths: &mut [&str], builder: &TermMatcherBuilder) -> Result<String> {
     paths.sort();
 
     // Craft all stamps and concatenate them into the hasher
     let mut state = DefaultHasher::new();
+    CACHE_VERSION.hash(&mut state);
     for path in paths.iter() {
-        let stamp = if let Ok(_) = Url::parse(path) {
-            get_url_stamp(&path)?
+        let stamp = if is_sparql_source(path) {
+            SparqlSource::parse(path)?.stamp(path)?
+        } else if Url::parse(path).is_ok() {
+            get_url_stamp(path)?
         } else if PathBuf::from(path).exists() {
-            get_file_stamp(&path)?
+            get_file_stamp(path)?
         } else {
             return Err(anyhow::anyhow!("Invalid path: {}", path));
         };
         stamp.hash(&mut state);
     }
 
+    // Non-default configurations produce different terms from the same sources
+    if *builder != TermMatcherBuilder::default() {
+        builder.hash(&mut state);
+    }
+
     // Hash the concatenated stamps
     let key = state.finish();
 
@@ -59,9 +83,15 @@ pub fn get_cache_key(paths: &mut Vec<&str>) -> Result<String> {
 }
 
 /// Get the full cross-platform cache path for a collection of source paths.
-pub fn get_cache_path(sources: &mut Vec<&str>) -> Result<PathBuf> {
+pub fn get_cache_path(sources: &mut [&str]) -> Result<PathBuf> {
+    get_cache_path_with(sources, &TermMatcherBuilder::default())
+}
+
+/// Get the full cross-platform cache path for a collection of source paths
+/// gathered with a specific builder configuration.
+pub fn get_cache_path_with(sources: &mut [&str], builder: &TermMatcherBuilder) -> Result<PathBuf> {
     let cache_dir = dirs::cache_dir().unwrap().join("fuzon");
-    let cache_key = get_cache_key(sources)?;
+    let cache_key = get_cache_key_with(sources, builder)?;
     let cache_path = cache_dir.join(&cache_key);
 
     Ok(cache_path)
@@ -71,7 +101,7 @@ pub fn get_cache_path(sources: &mut Vec<&str>) -> Result<PathBuf> {
 pub fn cache_by_source(sources: Vec<&str>) -> Result<()> {
     for source in sources {
         let matcher = TermMatcher::from_paths(vec![source])?;
-        let cache_path = get_cache_path(&mut vec![source])?;
+        let cache_path = get_cache_path(&mut [source])?;
         matcher.dump(&cache_path)?;
     }
 
@@ -80,10 +110,10 @@ pub fn cache_by_source(sources: Vec<&str>) -> Result<()> {
 
 /// Load and combine single-source cache entries into a combined TermMatcher.
 pub fn load_by_source(sources: Vec<&str>) -> Result<TermMatcher> {
-    let mut matcher: TermMatcher = TermMatcher { terms: Vec::new() };
+    let mut matcher = TermMatcher::new();
 
     for source in sources {
-        let cache_path = get_cache_path(&mut vec![source])?;
+        let cache_path = get_cache_path(&mut [source])?;
         matcher = matcher + TermMatcher::load(&cache_path)?;
     }
 
@@ -115,4 +145,24 @@ mod tests {
         let key = get_cache_key(&mut sources).unwrap();
         assert!(path.ends_with(key));
     }
+
+    #[test]
+    fn builder_key() {
+        let mut sources = vec!["Cargo.toml"];
+        let default = get_cache_key(&mut sources).unwrap();
+        let same = get_cache_key_with(&mut sources, &TermMatcherBuilder::default()).unwrap();
+        let builder = TermMatcher::builder().with_predicate("http://example.org/label");
+        let custom = get_cache_key_with(&mut sources, &builder).unwrap();
+        assert_eq!(default, same);
+        assert_ne!(default, custom);
+    }
+
+    #[test]
+    fn versioned_key() {
+        let mut sources = vec!["Cargo.toml"];
+        let mut state = DefaultHasher::new();
+        get_file_stamp("Cargo.toml").unwrap().hash(&mut state);
+        let unversioned = state.finish().to_string();
+        assert_ne!(get_cache_key(&mut sources).unwrap(), unversioned);
+    }
 }
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// IAO "definition" annotation, used by OBO ontologies.
+pub(crate) const IAO_DEFINITION: &str = "http://purl.obolibrary.org/obo/IAO_0000115";
+pub(crate) const SKOS_DEFINITION: &str = "http://www.w3.org/2004/02/skos/core#definition";
+pub(crate) const RDFS_COMMENT: &str = "http://www.w3.org/2000/01/rdf-schema#comment";
+
+/// Predicates of definitions gathered by default, by decreasing priority.
+pub const DEFINITION_PREDICATES: [&str; 3] = [IAO_DEFINITION, SKOS_DEFINITION, RDFS_COMMENT];
+
+/// One definition (or description) per URI, to tell apart terms with
+/// similar labels. URIs use the same representation as `Term::uri`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Definitions {
+    definitions: HashMap<String, String>,
+}
+
+impl Definitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the definition of a URI, replacing any previous one.
+    pub fn insert(&mut self, uri: &str, definition: &str) {
+        self.definitions
+            .insert(uri.to_string(), definition.to_string());
+    }
+
+    /// Add the definitions of another set, keeping existing ones.
+    pub fn merge(&mut self, other: Definitions) {
+        for (uri, definition) in other.definitions {
+            self.definitions.entry(uri).or_insert(definition);
+        }
+    }
+
+    /// Definition of a URI, if any.
+    pub fn get(&self, uri: &str) -> Option<&str> {
+        self.definitions.get(uri).map(|d| d.as_str())
+    }
+
+    /// URIs and their definition, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.definitions
+            .iter()
+            .map(|(uri, definition)| (uri.as_str(), definition.as_str()))
+    }
+
+    /// Number of defined URIs.
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definitions() {
+        let mut definitions = Definitions::new();
+        definitions.insert("a", "first");
+        definitions.insert("a", "second");
+
+        let mut other = Definitions::new();
+        other.insert("a", "other");
+        other.insert("b", "only");
+        definitions.merge(other);
+
+        assert_eq!(definitions.get("a"), Some("second"));
+        assert_eq!(definitions.get("b"), Some("only"));
+        assert_eq!(definitions.get("c"), None);
+        assert_eq!(definitions.len(), 2);
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+pub(crate) const OWL_DEPRECATED: &str = "http://www.w3.org/2002/07/owl#deprecated";
+/// IAO "term replaced by" annotation.
+pub(crate) const IAO_REPLACED_BY: &str = "http://purl.obolibrary.org/obo/IAO_0100001";
+
+/// Predicates marking deprecated terms, always gathered.
+pub const DEPRECATION_PREDICATES: [&str; 2] = [OWL_DEPRECATED, IAO_REPLACED_BY];
+
+/// Whether a label marks its term as obsolete, e.g. "obsolete cell".
+pub fn is_obsolete_label(label: &str) -> bool {
+    let label = label.trim_start().to_lowercase();
+    label.starts_with("obsolete ") || label.starts_with("obsolete_")
+}
+
+/// Deprecated URIs along with their replacements, if any.
+/// URIs use the same representation as `Term::uri`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Deprecations {
+    replaced_by: HashMap<String, Vec<String>>,
+}
+
+impl Deprecations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a URI as deprecated.
+    pub fn deprecate(&mut self, uri: &str) {
+        self.replaced_by.entry(uri.to_string()).or_default();
+    }
+
+    /// Mark a URI as deprecated in favour of another one. Duplicates are ignored.
+    pub fn add_replacement(&mut self, uri: &str, replacement: &str) {
+        let replacements = self.replaced_by.entry(uri.to_string()).or_default();
+        if !replacements.iter().any(|r| r == replacement) {
+            replacements.push(replacement.to_string());
+        }
+    }
+
+    /// Add all deprecations of another set.
+    pub fn merge(&mut self, other: Deprecations) {
+        for (uri, replacements) in other.replaced_by {
+            self.deprecate(&uri);
+            for replacement in replacements {
+                self.add_replacement(&uri, &replacement);
+            }
+        }
+    }
+
+    pub fn is_deprecated(&self, uri: &str) -> bool {
+        self.replaced_by.contains_key(uri)
+    }
+
+    /// Replacements of a deprecated URI, empty if it has none or is not deprecated.
+    pub fn replaced_by(&self, uri: &str) -> &[String] {
+        self.replaced_by.get(uri).map_or(&[], |r| r.as_slice())
+    }
+
+    /// Number of deprecated URIs.
+    pub fn len(&self) -> usize {
+        self.replaced_by.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replaced_by.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deprecations() {
+        let mut deprecations = Deprecations::new();
+        deprecations.deprecate("a");
+        deprecations.add_replacement("b", "c");
+        deprecations.add_replacement("b", "c");
+
+        let mut other = Deprecations::new();
+        other.add_replacement("a", "d");
+        deprecations.merge(other);
+
+        assert!(deprecations.is_deprecated("a"));
+        assert!(!deprecations.is_deprecated("c"));
+        assert_eq!(deprecations.replaced_by("a"), ["d"]);
+        assert_eq!(deprecations.replaced_by("b"), ["c"]);
+        assert_eq!(deprecations.len(), 2);
+
+        assert!(is_obsolete_label("obsolete cell"));
+        assert!(is_obsolete_label("OBSOLETE_cell"));
+        assert!(!is_obsolete_label("obsolescence"));
+    }
+}
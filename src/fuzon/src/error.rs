@@ -0,0 +1,103 @@
+use core::fmt;
+
+use oxrdfio::RdfParseError;
+
+/// Error raised while gathering terms from a source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IngestError {
+    /// Path, URL or name of the source.
+    pub source: String,
+    /// Line of the error (starting at 1), if known.
+    pub line: Option<u64>,
+    /// Column of the error (starting at 1), if known.
+    pub column: Option<u64>,
+    /// Byte offset of the error from the start of the source, if known.
+    pub offset: Option<u64>,
+    pub message: String,
+}
+
+impl IngestError {
+    /// An error without location in the source, e.g. when it cannot be read.
+    pub fn new(source: &str, message: impl fmt::Display) -> Self {
+        IngestError {
+            source: source.to_string(),
+            line: None,
+            column: None,
+            offset: None,
+            message: message.to_string(),
+        }
+    }
+
+    /// Locate the error at a line (starting at 1) of the source.
+    pub(crate) fn at_line(mut self, line: Option<u64>) -> Self {
+        self.line = line;
+        self
+    }
+
+    pub(crate) fn from_parse_error(source: &str, error: RdfParseError) -> Self {
+        let location = match &error {
+            RdfParseError::Syntax(e) => e.location().map(|l| l.start),
+            RdfParseError::Io(_) => None,
+        };
+
+        let message = error.to_string();
+        // Messages of located errors start with their location, which is rendered separately
+        let message = match location {
+            Some(_) => message
+                .split_once(": ")
+                .map_or(message.clone(), |(_, m)| m.to_string()),
+            None => message,
+        };
+
+        IngestError {
+            source: source.to_string(),
+            line: location.map(|l| l.line + 1),
+            column: location.map(|l| l.column + 1),
+            offset: location.map(|l| l.offset),
+            message,
+        }
+    }
+}
+
+/// Renders as `source:line:column: message`, with the location when known.
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for IngestError {}
+
+/// Summary of terms gathered from a single source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceReport {
+    /// Path, URL or name of the source.
+    pub source: String,
+    /// Number of terms gathered.
+    pub terms: usize,
+    /// Number of invalid triples skipped in lenient mode.
+    pub errors: usize,
+    /// First invalid triple skipped, if any.
+    pub first_error: Option<IngestError>,
+}
+
+impl fmt::Display for SourceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} terms", self.source, self.terms)?;
+        if let Some(error) = &self.first_error {
+            write!(f, ", {} parse errors (first", self.errors)?;
+            if let Some(line) = error.line {
+                write!(f, " at line {}", line)?;
+            }
+            write!(f, ": {})", error.message)?;
+        }
+        Ok(())
+    }
+}
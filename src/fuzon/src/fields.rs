@@ -0,0 +1,191 @@
+use core::fmt;
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{score_terms, Scorer, Term, SKOS_ALT_LABEL};
+
+/// Weights of the fields of a URI when ranking by fields, see
+/// `SearchOptions::with_fields`. Labels and synonyms are scored by fuzzy
+/// matching (see `SearchOptions::scorer`), and the definition by full-text
+/// matching (BM25), each between 0 and 1. The score of a URI is the weighted average of its fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldWeights {
+    /// Weight of the best matching label (e.g. rdfs:label, skos:prefLabel).
+    pub label: f64,
+    /// Weight of the best matching synonym (skos:altLabel or oboInOwl synonyms).
+    pub synonyms: f64,
+    /// Weight of the definition, see `TermMatcher::definitions`.
+    pub definition: f64,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        FieldWeights {
+            label: 1.0,
+            synonyms: 0.7,
+            definition: 0.5,
+        }
+    }
+}
+
+impl FieldWeights {
+    /// Default weights with some fields ("label", "synonyms", "definition")
+    /// overridden. Weights must be non-negative, and at least one positive.
+    pub fn from_weights<'a>(
+        weights: impl IntoIterator<Item = (&'a str, f64)>,
+    ) -> Result<Self, String> {
+        let mut fields = FieldWeights::default();
+        for (field, weight) in weights {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(format!("invalid weight for {}: {}", field, weight));
+            }
+            match field.trim().to_lowercase().as_str() {
+                "label" => fields.label = weight,
+                "synonyms" | "synonym" => fields.synonyms = weight,
+                "definition" => fields.definition = weight,
+                _ => return Err(format!("unknown field: {}", field)),
+            }
+        }
+        if fields.total() == 0.0 {
+            return Err("at least one field weight must be positive".to_string());
+        }
+
+        Ok(fields)
+    }
+
+    fn total(&self) -> f64 {
+        self.label + self.synonyms + self.definition
+    }
+}
+
+impl FromStr for FieldWeights {
+    type Err = String;
+
+    /// Parse comma-separated weights, e.g. "label=1,definition=2". Missing
+    /// fields keep their default weight, and "" or "default" gives the defaults.
+    fn from_str(weights: &str) -> Result<Self, Self::Err> {
+        let weights = weights.trim();
+        if weights.is_empty() || weights == "default" {
+            return Ok(FieldWeights::default());
+        }
+        let weights = weights
+            .split(',')
+            .map(|pair| {
+                let (field, weight) = pair
+                    .split_once(['=', ':'])
+                    .ok_or_else(|| format!("expected field=weight: {}", pair))?;
+                let weight = weight
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid weight for {}: {}", field, weight))?;
+                Ok((field, weight))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        FieldWeights::from_weights(weights)
+    }
+}
+
+impl fmt::Display for FieldWeights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "label={},synonyms={},definition={}",
+            self.label, self.synonyms, self.definition
+        )
+    }
+}
+
+// Whether a term is a synonym rather than a label of its URI.
+fn is_synonym(term: &Term) -> bool {
+    term.predicate == SKOS_ALT_LABEL || term.synonym_scope().is_some()
+}
+
+// Best term and field scores of a URI.
+struct UriScores {
+    // Position of the first term of the URI, to break ties
+    first: usize,
+    // Position of the term representing the URI
+    term: usize,
+    label: f64,
+    synonyms: f64,
+}
+
+/// Returns the n best URIs of the terms by the weighted scores of their
+/// fields, each represented by its best matching term, along with their
+/// score between 0 and 1. Labels and synonyms are scored with the scorer,
+/// using the label paired with each term (e.g. its normalized label), and
+/// definition scores are given by URI, see `TextIndex::scores`. URIs
+/// matching no field are dropped, and ties are ordered by position in the input.
+pub fn rank_top_fields<'a, L: AsRef<str> + Sync>(
+    query: &str,
+    terms: &[(&'a Term, L)],
+    n: usize,
+    weights: &FieldWeights,
+    definitions: &HashMap<&str, f64>,
+    scorer: &dyn Scorer,
+) -> Vec<(&'a Term, f64)> {
+    let mut fuzzy = vec![0.0; terms.len()];
+    for (i, _, score) in score_terms(query, terms, scorer) {
+        fuzzy[i] = scorer.normalize(score, query);
+    }
+
+    let mut uris: HashMap<&str, UriScores> = HashMap::new();
+    for (i, (term, _)) in terms.iter().enumerate() {
+        let scores = uris.entry(term.uri.as_str()).or_insert(UriScores {
+            first: i,
+            term: i,
+            label: 0.0,
+            synonyms: 0.0,
+        });
+        let field = if is_synonym(term) {
+            &mut scores.synonyms
+        } else {
+            &mut scores.label
+        };
+        *field = field.max(fuzzy[i]);
+        // Represent the URI by its best matching term, preferably a label
+        let best = (fuzzy[scores.term], !is_synonym(terms[scores.term].0));
+        if (fuzzy[i], !is_synonym(term)) > best {
+            scores.term = i;
+        }
+    }
+
+    let total = weights.total();
+    let mut ranked: Vec<(usize, &Term, f64)> = uris
+        .into_iter()
+        .map(|(uri, scores)| {
+            let definition = definitions.get(uri).copied().unwrap_or(0.0);
+            let score = (weights.label * scores.label
+                + weights.synonyms * scores.synonyms
+                + weights.definition * definition)
+                / total;
+            (scores.first, terms[scores.term].0, score)
+        })
+        .filter(|(_, _, score)| *score > 0.0)
+        .collect();
+    ranked.sort_unstable_by(|a, b| b.2.total_cmp(&a.2).then(a.0.cmp(&b.0)));
+    ranked.truncate(n);
+
+    ranked.into_iter().map(|(_, t, s)| (t, s)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_weights() {
+        assert_eq!("".parse::<FieldWeights>(), Ok(FieldWeights::default()));
+        let weights: FieldWeights = "label=2, definition:0".parse().unwrap();
+        assert_eq!(weights.label, 2.0);
+        assert_eq!(weights.synonyms, FieldWeights::default().synonyms);
+        assert_eq!(weights.definition, 0.0);
+        assert_eq!(weights.to_string().parse::<FieldWeights>(), Ok(weights));
+        assert!("label".parse::<FieldWeights>().is_err());
+        assert!("label=-1".parse::<FieldWeights>().is_err());
+        assert!("comment=1".parse::<FieldWeights>().is_err());
+        assert!("label=0,synonyms=0,definition=0"
+            .parse::<FieldWeights>()
+            .is_err());
+    }
+}
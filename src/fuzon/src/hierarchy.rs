@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+pub(crate) const RDFS_SUBCLASS_OF: &str = "http://www.w3.org/2000/01/rdf-schema#subClassOf";
+const SKOS_BROADER: &str = "http://www.w3.org/2004/02/skos/core#broader";
+
+/// Predicates linking a class or concept to its parents.
+pub const HIERARCHY_PREDICATES: [&str; 2] = [RDFS_SUBCLASS_OF, SKOS_BROADER];
+
+/// Parent edges between URIs (rdfs:subClassOf, skos:broader).
+/// URIs use the same representation as `Term::uri`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Hierarchy {
+    parents: HashMap<String, Vec<String>>,
+}
+
+impl Hierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that parent is a direct parent of child. Duplicate edges are ignored.
+    pub fn add_edge(&mut self, child: &str, parent: &str) {
+        let parents = self.parents.entry(child.to_string()).or_default();
+        if !parents.iter().any(|p| p == parent) {
+            parents.push(parent.to_string());
+        }
+    }
+
+    /// Add all edges of another hierarchy.
+    pub fn merge(&mut self, other: Hierarchy) {
+        for (child, parents) in other.parents {
+            for parent in parents {
+                self.add_edge(&child, &parent);
+            }
+        }
+    }
+
+    /// Direct parents of a URI, in insertion order.
+    pub fn parents(&self, uri: &str) -> &[String] {
+        self.parents.get(uri).map_or(&[], |p| p.as_slice())
+    }
+
+    /// All ancestors of a URI, nearest first. Cycles are ignored.
+    pub fn ancestors(&self, uri: &str) -> Vec<&str> {
+        let mut seen = HashSet::from([uri]);
+        let mut ancestors = Vec::new();
+        let mut queue = VecDeque::from([uri]);
+        while let Some(current) = queue.pop_front() {
+            for parent in self.parents(current) {
+                if seen.insert(parent.as_str()) {
+                    ancestors.push(parent.as_str());
+                    queue.push_back(parent.as_str());
+                }
+            }
+        }
+
+        ancestors
+    }
+
+    /// Path from a URI to a root following the first parent at each step,
+    /// nearest first. Useful to show a single breadcrumb.
+    pub fn lineage(&self, uri: &str) -> Vec<&str> {
+        let mut seen = HashSet::from([uri]);
+        let mut lineage = Vec::new();
+        let mut current = uri;
+        while let Some(parent) = self.parents(current).first() {
+            if !seen.insert(parent.as_str()) {
+                break;
+            }
+            lineage.push(parent.as_str());
+            current = parent;
+        }
+
+        lineage
+    }
+
+    /// Number of URIs with at least one parent.
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+}
+
+/// Children of URIs, inverting the parent edges of a hierarchy to find
+/// descendants without scanning all edges.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ChildIndex {
+    children: HashMap<String, Vec<String>>,
+}
+
+impl ChildIndex {
+    pub fn new(hierarchy: &Hierarchy) -> Self {
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        for (child, parents) in &hierarchy.parents {
+            for parent in parents {
+                children
+                    .entry(parent.clone())
+                    .or_default()
+                    .push(child.clone());
+            }
+        }
+
+        ChildIndex { children }
+    }
+
+    /// Direct children of a URI, in no particular order.
+    pub fn children(&self, uri: &str) -> &[String] {
+        self.children.get(uri).map_or(&[], |c| c.as_slice())
+    }
+
+    /// All descendants of a URI, i.e. URIs having it as an ancestor.
+    pub fn descendants(&self, uri: &str) -> HashSet<&str> {
+        let mut descendants = HashSet::new();
+        let mut queue = VecDeque::from([uri]);
+        while let Some(current) = queue.pop_front() {
+            for child in self.children(current) {
+                if child != uri && descendants.insert(child.as_str()) {
+                    queue.push_back(child.as_str());
+                }
+            }
+        }
+
+        descendants
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ancestors() {
+        let mut hierarchy = Hierarchy::new();
+        hierarchy.add_edge("leukocyte", "hematopoietic cell");
+        hierarchy.add_edge("leukocyte", "motile cell");
+        hierarchy.add_edge("hematopoietic cell", "cell");
+        hierarchy.add_edge("motile cell", "cell");
+        hierarchy.add_edge("motile cell", "motile cell");
+        hierarchy.add_edge("leukocyte", "motile cell");
+
+        assert_eq!(
+            hierarchy.parents("leukocyte"),
+            ["hematopoietic cell", "motile cell"]
+        );
+        assert_eq!(
+            hierarchy.ancestors("leukocyte"),
+            vec!["hematopoietic cell", "motile cell", "cell"]
+        );
+        assert_eq!(
+            hierarchy.lineage("leukocyte"),
+            vec!["hematopoietic cell", "cell"]
+        );
+        assert!(hierarchy.ancestors("cell").is_empty());
+        let children = ChildIndex::new(&hierarchy);
+        assert_eq!(children.children("hematopoietic cell"), ["leukocyte"]);
+        assert_eq!(
+            children.descendants("cell"),
+            HashSet::from(["hematopoietic cell", "motile cell", "leukocyte"])
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        let mut hierarchy = Hierarchy::new();
+        hierarchy.add_edge("a", "b");
+        hierarchy.add_edge("b", "c");
+        hierarchy.add_edge("c", "a");
+        assert_eq!(hierarchy.ancestors("a"), vec!["b", "c"]);
+        assert_eq!(hierarchy.lineage("b"), vec!["c", "a"]);
+        assert_eq!(
+            ChildIndex::new(&hierarchy).descendants("a"),
+            HashSet::from(["b", "c"])
+        );
+
+        let mut other = Hierarchy::new();
+        other.add_edge("a", "d");
+        hierarchy.merge(other);
+        assert_eq!(hierarchy.parents("a"), ["b", "d"]);
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Character bitmask index used to prune terms before scoring.
+/// A fuzzy match requires all query characters to appear in the label,
+/// so a label can only match if its mask contains all bits of the query mask.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CharIndex {
+    masks: Vec<u64>,
+}
+
+impl CharIndex {
+    /// Build the index from labels, in the same order as the terms.
+    pub fn new<'a>(labels: impl Iterator<Item = &'a str>) -> Self {
+        CharIndex {
+            masks: labels.map(char_mask).collect(),
+        }
+    }
+
+    pub fn push(&mut self, label: &str) {
+        self.masks.push(char_mask(label));
+    }
+
+    pub fn len(&self) -> usize {
+        self.masks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+
+    /// Whether the label at position i may match a query with the input mask.
+    pub fn is_candidate(&self, i: usize, query_mask: u64) -> bool {
+        self.masks[i] & query_mask == query_mask
+    }
+}
+
+/// Hash index from URIs to the positions of their terms, for exact lookups.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct UriIndex {
+    positions: HashMap<String, Vec<usize>>,
+    len: usize,
+}
+
+impl UriIndex {
+    /// Build the index from URIs, in the same order as the terms.
+    pub fn new<'a>(uris: impl Iterator<Item = &'a str>) -> Self {
+        let mut index = UriIndex::default();
+        for uri in uris {
+            index.push(uri);
+        }
+        index
+    }
+
+    pub fn push(&mut self, uri: &str) {
+        self.positions
+            .entry(uri.to_string())
+            .or_default()
+            .push(self.len);
+        self.len += 1;
+    }
+
+    /// Number of indexed terms.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Positions of the terms of a URI, in increasing order.
+    pub fn get(&self, uri: &str) -> &[usize] {
+        self.positions.get(uri).map_or(&[], |p| p.as_slice())
+    }
+}
+
+// BM25 parameters: term frequency saturation and length normalization.
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Inverted index of the words of texts (e.g. definitions), for full-text
+/// search with BM25. Each text is identified by a key, e.g. its URI.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TextIndex {
+    keys: Vec<String>,
+    // Number of words of each text
+    lengths: Vec<u32>,
+    // Texts containing each word, with the number of occurrences
+    postings: HashMap<String, Vec<(u32, u32)>>,
+}
+
+impl TextIndex {
+    /// Build the index from texts along with their key.
+    /// Texts are sorted by key, so that equal inputs give equal indexes.
+    pub fn new<'a>(texts: impl Iterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut texts: Vec<(&str, &str)> = texts.collect();
+        texts.sort_unstable();
+        let mut index = TextIndex::default();
+        for (key, text) in texts {
+            let id = index.keys.len() as u32;
+            let words = words(text);
+            let mut counts: HashMap<&str, u32> = HashMap::new();
+            for word in &words {
+                *counts.entry(word).or_default() += 1;
+            }
+            for (word, count) in counts {
+                index
+                    .postings
+                    .entry(word.to_string())
+                    .or_default()
+                    .push((id, count));
+            }
+            index.keys.push(key.to_string());
+            index.lengths.push(words.len() as u32);
+        }
+        index
+    }
+
+    /// Number of indexed texts.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// BM25 scores of the texts containing any word of the query, by key.
+    /// Scores are divided by the sum of the inverse document frequencies of
+    /// the query words, so that texts of average length containing each
+    /// word once score about 1. Scores are capped to 1.
+    pub fn scores(&self, query: &str) -> HashMap<&str, f64> {
+        let n = self.keys.len() as f64;
+        let mut query_words = words(query);
+        query_words.sort_unstable();
+        query_words.dedup();
+        if n == 0.0 || query_words.is_empty() {
+            return HashMap::new();
+        }
+        let avg_len = (self.lengths.iter().map(|l| *l as f64).sum::<f64>() / n).max(1.0);
+
+        let mut total_idf = 0.0;
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+        for word in &query_words {
+            let postings = self.postings.get(word).map_or(&[][..], |p| p.as_slice());
+            let df = postings.len() as f64;
+            let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+            total_idf += idf;
+            for (id, count) in postings {
+                let tf = *count as f64;
+                let len = self.lengths[*id as usize] as f64;
+                let saturation = BM25_K1 * (1.0 - BM25_B + BM25_B * len / avg_len);
+                *scores.entry(*id).or_default() += idf * tf * (BM25_K1 + 1.0) / (tf + saturation);
+            }
+        }
+
+        scores
+            .into_iter()
+            .map(|(id, score)| {
+                (
+                    self.keys[id as usize].as_str(),
+                    (score / total_idf).min(1.0),
+                )
+            })
+            .collect()
+    }
+}
+
+/// BK-tree of words (e.g. normalized labels) by Levenshtein distance, to
+/// find the words within a few edits of a misspelled query.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct BkTree {
+    nodes: Vec<BkNode>,
+    // Number of inserted words, including duplicates
+    len: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+struct BkNode {
+    word: String,
+    // Child nodes by distance to the word
+    children: Vec<(u32, u32)>,
+}
+
+impl BkTree {
+    /// Build the tree from words, in the same order as the terms.
+    pub fn new<'a>(words: impl Iterator<Item = &'a str>) -> Self {
+        let mut tree = BkTree::default();
+        for word in words {
+            tree.push(word);
+        }
+        tree
+    }
+
+    /// Insert a word, duplicates are only counted.
+    pub fn push(&mut self, word: &str) {
+        self.len += 1;
+        let new = BkNode {
+            word: word.to_string(),
+            children: Vec::new(),
+        };
+        if self.nodes.is_empty() {
+            self.nodes.push(new);
+            return;
+        }
+        let mut node = 0;
+        loop {
+            let distance = strsim::levenshtein(&self.nodes[node].word, word) as u32;
+            if distance == 0 {
+                return;
+            }
+            match self.nodes[node]
+                .children
+                .iter()
+                .find(|(d, _)| *d == distance)
+            {
+                Some((_, child)) => node = *child as usize,
+                None => {
+                    let child = self.nodes.len() as u32;
+                    self.nodes[node].children.push((distance, child));
+                    self.nodes.push(new);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Number of inserted words, including duplicates.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Distinct words within the maximum distance of the query, along with
+    /// their distance.
+    pub fn find(&self, query: &str, max_distance: usize) -> Vec<(&str, usize)> {
+        let mut found = Vec::new();
+        let mut pending = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(node) = pending.pop() {
+            let node = &self.nodes[node];
+            let distance = strsim::levenshtein(&node.word, query);
+            if distance <= max_distance {
+                found.push((node.word.as_str(), distance));
+            }
+            // Words within the distance of the query are within the distance
+            // of the node plus or minus the maximum distance (triangle inequality)
+            let range = distance.saturating_sub(max_distance)..=distance + max_distance;
+            pending.extend(
+                node.children
+                    .iter()
+                    .filter(|(d, _)| range.contains(&(*d as usize)))
+                    .map(|(_, child)| *child as usize),
+            );
+        }
+        found
+    }
+}
+
+/// Lowercased words (runs of letters and digits) of a text.
+pub fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Bitmask of the characters present in a text. Letters and digits have
+/// dedicated bits, other characters share the remaining ones.
+/// Characters are lowercased to match case-insensitively.
+pub fn char_mask(text: &str) -> u64 {
+    text.chars().fold(0, |mask, c| mask | 1 << char_bit(c))
+}
+
+// Bit position of a character in the mask.
+fn char_bit(c: char) -> u32 {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    match c {
+        'a'..='z' => c as u32 - 'a' as u32,
+        '0'..='9' => 26 + c as u32 - '0' as u32,
+        _ => 36 + c as u32 % 28,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates() {
+        let index = CharIndex::new(["Leukocyte", "red blood cell", "Schrödinger"].into_iter());
+        let candidates = |query: &str| {
+            let mask = char_mask(query);
+            (0..index.len())
+                .filter(|i| index.is_candidate(*i, mask))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(candidates("kocyte"), vec![0]);
+        assert_eq!(candidates("LEUKO"), vec![0]);
+        assert_eq!(candidates("ÖDI"), vec![2]);
+        assert_eq!(candidates("e"), vec![0, 1, 2]);
+        assert_eq!(candidates(""), vec![0, 1, 2]);
+        assert!(candidates("xyz").is_empty());
+    }
+
+    #[test]
+    fn uri_positions() {
+        let index = UriIndex::new(["a", "b", "a"].into_iter());
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get("a"), [0, 2]);
+        assert_eq!(index.get("b"), [1]);
+        assert!(index.get("c").is_empty());
+    }
+
+    #[test]
+    fn bk_tree() {
+        let tree =
+            BkTree::new(["lymphocyte", "leukocyte", "lymphocyte", "cell", "b cell"].into_iter());
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.find("lymphocite", 1), [("lymphocyte", 1)]);
+        let mut found = tree.find("cel", 3);
+        found.sort_unstable();
+        assert_eq!(found, [("b cell", 3), ("cell", 1)]);
+        assert!(tree.find("neuron", 2).is_empty());
+        assert!(BkTree::default().find("cell", 2).is_empty());
+    }
+
+    #[test]
+    fn text_scores() {
+        let index = TextIndex::new(
+            [
+                ("a", "Outer layer of an organ."),
+                ("b", "Layer of grey matter of the cerebrum."),
+                ("c", "A cell of the immune system."),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(words("Grey-matter, 2x"), ["grey", "matter", "2x"]);
+        let scores = index.scores("outer LAYER");
+        assert_eq!(scores.len(), 2);
+        // Rare words weigh more, and a full match of average length scores about 1
+        assert!(scores["a"] > scores["b"]);
+        assert!(scores["a"] > 0.9 && scores["a"] <= 1.0);
+        assert!(index.scores("neuron").is_empty());
+        assert!(index.scores("").is_empty());
+    }
+}
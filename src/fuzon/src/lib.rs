@@ -1,48 +1,630 @@
 use core::fmt;
 use std::{
-    collections::HashSet,
-    fs::File,
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     hash::Hash,
-    io::{BufRead, BufReader},
+    io::BufRead,
     ops::Add,
     path::Path,
 };
 
 use anyhow::Result;
-use lazy_static::lazy_static;
-use oxrdfio::{RdfFormat, RdfParser};
-use oxrdf::Subject;
-use postcard;
-use reqwest::{blocking::Client, Url};
+use oxrdf::{Quad, Subject, Term as RdfTerm};
+use oxrdfio::{RdfFormat, RdfParseError, RdfParser};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use rff;
-
 pub mod cache;
+pub mod definition;
+pub mod deprecation;
+pub mod error;
+pub mod fields;
+pub mod hierarchy;
+pub mod index;
+pub mod normalize;
+pub mod obo;
+pub mod prefix;
+pub mod scorer;
+pub mod source;
+pub mod sparql;
+pub mod table;
+pub mod types;
 pub mod ui;
 
-// HashMap of common annotation properties
-lazy_static! {
-    static ref ANNOTATIONS: HashSet<String> = {
-        HashSet::from_iter(
-            vec![
-                "http://www.w3.org/2000/01/rdf-schema#label".to_string(),
-                "http://schema.org/name".to_string(),
-                "http://www.w3.org/2004/02/skos/core#prefLabel".to_string(),
-                "http://www.w3.org/2004/02/skos/core#altLabel".to_string(),
-                "http://xmlns.com/foaf/0.1/name".to_string(),
-                "http://purl.org/dc/elements/1.1/title".to_string(),
-                "http://xmlns.com/foaf/0.1/name".to_string(),
-            ]
+pub use definition::{Definitions, DEFINITION_PREDICATES};
+use deprecation::{is_obsolete_label, IAO_REPLACED_BY, OWL_DEPRECATED};
+pub use deprecation::{Deprecations, DEPRECATION_PREDICATES};
+pub use error::{IngestError, SourceReport};
+use fields::rank_top_fields;
+pub use fields::FieldWeights;
+pub use hierarchy::{ChildIndex, Hierarchy, HIERARCHY_PREDICATES};
+use index::{char_mask, BkTree, CharIndex, TextIndex, UriIndex};
+pub use normalize::Normalizer;
+pub use obo::SynonymScope;
+use obo::{OboError, OboParser};
+use prefix::split_curie;
+pub use prefix::PrefixMap;
+use scorer::{max_typos, TypoScorer};
+pub use scorer::{Scorer, ScorerKind};
+pub use source::{
+    get_reader, get_source, get_source_with_format, parse_format, parse_source_format, SourceFormat,
+};
+use sparql::{is_sparql_source, SparqlError, SparqlSource};
+use table::{gather_table, TableFormat, TableMapping};
+use types::RDF_TYPE;
+pub use types::{TermKind, TypeFilter, TypeIndex};
+
+/// Common annotation properties used to gather labels by default.
+pub const DEFAULT_PREDICATES: [&str; 6] = [
+    "http://www.w3.org/2000/01/rdf-schema#label",
+    "http://schema.org/name",
+    "http://www.w3.org/2004/02/skos/core#prefLabel",
+    "http://www.w3.org/2004/02/skos/core#altLabel",
+    "http://xmlns.com/foaf/0.1/name",
+    "http://purl.org/dc/elements/1.1/title",
+];
+
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const SKOS_PREF_LABEL: &str = "http://www.w3.org/2004/02/skos/core#prefLabel";
+const SKOS_ALT_LABEL: &str = "http://www.w3.org/2004/02/skos/core#altLabel";
+
+// Failure to read a quad from a source. Invalid content can be skipped in
+// lenient mode, but not failures to read the source.
+enum QuadError {
+    Invalid(IngestError),
+    Fatal(IngestError),
+}
+
+/// Configures how terms are gathered from sources when building a TermMatcher.
+/// Starts from the default annotation predicates, which can be extended,
+/// pruned or replaced.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub struct TermMatcherBuilder {
+    predicates: BTreeSet<String>,
+    lenient: bool,
+    format: Option<SourceFormat>,
+    source_formats: BTreeMap<String, SourceFormat>,
+    table: TableMapping,
+    hierarchy: bool,
+    types: bool,
+    prefixes: PrefixMap,
+    definition_predicates: Vec<String>,
+    normalizer: Normalizer,
+}
+
+impl Default for TermMatcherBuilder {
+    fn default() -> Self {
+        TermMatcherBuilder {
+            predicates: DEFAULT_PREDICATES.iter().map(|p| p.to_string()).collect(),
+            lenient: false,
+            format: None,
+            source_formats: BTreeMap::new(),
+            table: TableMapping::default(),
+            hierarchy: false,
+            types: false,
+            prefixes: PrefixMap::default(),
+            definition_predicates: DEFINITION_PREDICATES
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            normalizer: Normalizer::default(),
+        }
+    }
+}
+
+impl TermMatcherBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an annotation predicate (full IRI) to the set used for labels.
+    pub fn with_predicate(mut self, predicate: &str) -> Self {
+        self.predicates.insert(predicate.to_string());
+        self
+    }
+
+    /// Remove an annotation predicate (full IRI) from the set used for labels.
+    pub fn without_predicate(mut self, predicate: &str) -> Self {
+        self.predicates.remove(predicate);
+        self
+    }
+
+    /// Replace the whole set of annotation predicates used for labels.
+    pub fn with_predicates<I, S>(mut self, predicates: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.predicates = predicates.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add the oboInOwl synonym predicates of all scopes (exact, broad, narrow, related).
+    pub fn with_synonyms(mut self) -> Self {
+        self.predicates
+            .extend(SynonymScope::ALL.iter().map(|s| s.predicate().to_string()));
+        self
+    }
+
+    /// Annotation predicates currently configured.
+    pub fn predicates(&self) -> impl Iterator<Item = &str> {
+        self.predicates.iter().map(|p| p.as_str())
+    }
+
+    /// Skip invalid triples instead of failing on the first one.
+    /// Skipped triples are counted in each source's report.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Also gather parent edges (rdfs:subClassOf, skos:broader, OBO is_a)
+    /// between named nodes, see `TermMatcher::ancestors`.
+    pub fn hierarchy(mut self, hierarchy: bool) -> Self {
+        self.hierarchy = hierarchy;
+        self
+    }
+
+    /// Also gather the rdf:type of named nodes (OBO terms are classes),
+    /// see `SearchOptions::with_type`.
+    pub fn types(mut self, types: bool) -> Self {
+        self.types = types;
+        self
+    }
+
+    /// Prefixes used to compact URIs, in addition to those declared in
+    /// sources. They take precedence over source declarations.
+    pub fn with_prefixes(mut self, prefixes: PrefixMap) -> Self {
+        self.prefixes.merge(prefixes);
+        self
+    }
+
+    /// Replace the predicates (full IRIs) of definitions, by decreasing priority.
+    /// Without predicates, definitions are not gathered.
+    pub fn with_definition_predicates<I, S>(mut self, predicates: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.definition_predicates = predicates.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Predicates of definitions currently configured, by decreasing priority.
+    pub fn definition_predicates(&self) -> impl Iterator<Item = &str> {
+        self.definition_predicates.iter().map(|p| p.as_str())
+    }
+
+    /// Normalization of labels and queries in the matcher, see `TermMatcher::with_normalizer`.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Parse all sources with this format (RDF or tabular) instead of detecting it.
+    pub fn with_format(mut self, format: impl Into<SourceFormat>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Parse a specific source (path or URL) with this format instead of detecting it.
+    /// Takes precedence over `with_format`.
+    pub fn with_source_format(mut self, source: &str, format: impl Into<SourceFormat>) -> Self {
+        self.source_formats
+            .insert(source.to_string(), format.into());
+        self
+    }
+
+    /// Format overriding detection for a source, if any.
+    pub fn source_format(&self, source: &str) -> Option<SourceFormat> {
+        self.source_formats.get(source).copied().or(self.format)
+    }
+
+    /// Columns holding identifiers, labels and synonyms in tabular sources.
+    pub fn with_table_mapping(mut self, mapping: TableMapping) -> Self {
+        self.table = mapping;
+        self
+    }
+
+    /// Load terms from a tabular source (CSV, TSV or JSON lines) using the
+    /// configured column mapping. Invalid rows are skipped in lenient mode.
+    pub fn gather_table(
+        &self,
+        source: &str,
+        reader: impl BufRead,
+        format: TableFormat,
+    ) -> Result<(Gathered, SourceReport), IngestError> {
+        let (terms, report) = gather_table(source, reader, format, &self.table, self.lenient)?;
+        Ok((Gathered::from_terms(terms), report))
+    }
+
+    /// Load URI-label pairs from a single source using the configured predicates.
+    /// The source name is only used in errors and reports.
+    pub fn gather_source(
+        &self,
+        source: &str,
+        reader: impl BufRead,
+        format: RdfFormat,
+    ) -> Result<(Gathered, SourceReport), IngestError> {
+        // NOTE: May want to use bulk loader for better performances
+        let mut parser = RdfParser::from_format(format).for_reader(reader);
+        // Prefixes are read at the start, where they are usually declared,
+        // and at the end of the source
+        let mut prefixes = PrefixMap::new();
+        let mut started = false;
+        let quads = std::iter::from_fn(|| {
+            let quad = parser.next();
+            if !started || quad.is_none() {
+                prefixes.merge(parser.prefixes().collect());
+                started = true;
+            }
+            quad
+        })
+        .map(|quad| {
+            quad.map_err(|e| match e {
+                RdfParseError::Syntax(_) => {
+                    QuadError::Invalid(IngestError::from_parse_error(source, e))
+                }
+                RdfParseError::Io(_) => QuadError::Fatal(IngestError::from_parse_error(source, e)),
+            })
+        });
+        let (mut gathered, report) = self.gather_quads(source, quads)?;
+        gathered.prefixes = prefixes;
+
+        Ok((gathered, report))
+    }
+
+    /// Load URI-label pairs from a SPARQL source (`sparql+https://...`) using
+    /// the configured predicates. See `SparqlSource` for its parameters.
+    pub fn gather_sparql(&self, source: &str) -> Result<(Gathered, SourceReport), IngestError> {
+        let sparql = SparqlSource::parse(source).map_err(|e| IngestError::new(source, e))?;
+        let predicates = self
+            .predicates()
+            .chain(HIERARCHY_PREDICATES.into_iter().filter(|_| self.hierarchy))
+            .chain([RDF_TYPE].into_iter().filter(|_| self.types))
+            .chain(self.definition_predicates())
+            .chain(DEPRECATION_PREDICATES);
+        let quads = sparql.quads(predicates).map(|quad| {
+            quad.map_err(|e| match e {
+                SparqlError::InvalidTerm(_) => QuadError::Invalid(IngestError::new(source, e)),
+                SparqlError::Request(_) => QuadError::Fatal(IngestError::new(source, e)),
+            })
+        });
+
+        self.gather_quads(source, quads)
+    }
+
+    /// Load URI-label pairs from an OBO flat file using the configured predicates.
+    /// Names are labels (rdfs:label) and synonyms use oboInOwl predicates,
+    /// see `with_synonyms`.
+    pub fn gather_obo(
+        &self,
+        source: &str,
+        reader: impl BufRead,
+    ) -> Result<(Gathered, SourceReport), IngestError> {
+        let mut parser = OboParser::new(reader);
+        let quads = parser.by_ref().map(|quad| {
+            quad.map_err(|e| match e {
+                OboError::Syntax { line, message } => {
+                    QuadError::Invalid(IngestError::new(source, message).at_line(Some(line)))
+                }
+                OboError::Io(_) => QuadError::Fatal(IngestError::new(source, e)),
+            })
+        });
+        let (mut gathered, report) = self.gather_quads(source, quads)?;
+        // Idspaces are declared in the header
+        gathered.prefixes = parser.prefixes().collect();
+
+        Ok((gathered, report))
+    }
+
+    // Gather terms, definitions, deprecations, and parent edges and types if enabled,
+    // from the quads of a source.
+    // Invalid quads are skipped in lenient mode, and counted in the report.
+    fn gather_quads(
+        &self,
+        source: &str,
+        quads: impl Iterator<Item = Result<Quad, QuadError>>,
+    ) -> Result<(Gathered, SourceReport), IngestError> {
+        let mut gathered = Gathered::default();
+        // Rank of the definition kept for each URI, lower is better
+        let mut definition_ranks: HashMap<String, usize> = HashMap::new();
+        let mut errors = 0;
+        let mut first_error = None;
+        for quad in quads {
+            match quad {
+                Ok(quad) => {
+                    if self.hierarchy {
+                        if let Some((child, parent)) = extract_edge(&quad) {
+                            gathered.hierarchy.add_edge(&child, &parent);
+                        }
+                    }
+                    if self.types {
+                        if let Some((uri, rdf_type)) = extract_type(&quad) {
+                            gathered.types.add_type(&uri, &rdf_type);
+                        }
+                    }
+                    extract_deprecation(&quad, &mut gathered.deprecations);
+                    if let Some((uri, definition, rank)) = self.extract_definition(&quad) {
+                        if definition_ranks.get(&uri).map_or(true, |r| rank < *r) {
+                            gathered.definitions.insert(&uri, &definition);
+                            definition_ranks.insert(uri, rank);
+                        }
+                    }
+                    if let Some(term) = self.extract_term(quad) {
+                        // Only primary labels mark terms as obsolete, not their synonyms
+                        let primary =
+                            matches!(term.predicate.as_str(), RDFS_LABEL | SKOS_PREF_LABEL);
+                        if primary && is_obsolete_label(&term.label) {
+                            gathered.deprecations.deprecate(&term.uri);
+                        }
+                        gathered.terms.push(term);
+                    }
+                }
+                Err(QuadError::Invalid(e)) if self.lenient => {
+                    errors += 1;
+                    first_error.get_or_insert(e);
+                }
+                Err(QuadError::Invalid(e) | QuadError::Fatal(e)) => return Err(e),
+            }
+        }
+        let report = SourceReport {
+            source: source.to_string(),
+            terms: gathered.terms.len(),
+            errors,
+            first_error,
+        };
+
+        Ok((gathered, report))
+    }
+
+    // Convert a triple to a term if it annotates a named node with a label.
+    fn extract_term(&self, quad: Quad) -> Option<Term> {
+        // Drop blank nodes and filter by annotation properties
+        let Subject::NamedNode(subject) = quad.subject else {
+            return None;
+        };
+        if !self.predicates.contains(quad.predicate.as_str()) {
+            return None;
+        }
+        match quad.object {
+            RdfTerm::Literal(literal) => Some(Term {
+                uri: subject.into_string(),
+                label: literal.value().to_string(),
+                lang: literal.language().map(|l| l.to_string()),
+                predicate: quad.predicate.into_string(),
+            }),
+            // Labels are literals, skip resources
+            _ => None,
+        }
+    }
+
+    // Convert a triple to the definition of a named node along with its rank:
+    // definitions from earlier predicates come first, then those in English or
+    // without a language tag.
+    fn extract_definition(&self, quad: &Quad) -> Option<(String, String, usize)> {
+        let (Subject::NamedNode(uri), RdfTerm::Literal(literal)) = (&quad.subject, &quad.object)
+        else {
+            return None;
+        };
+        let position = self
+            .definition_predicates
             .iter()
-            .cloned(),
-        )
+            .position(|p| p == quad.predicate.as_str())?;
+        let definition = literal.value().trim();
+        if definition.is_empty() {
+            return None;
+        }
+        let english = literal
+            .language()
+            .map_or(true, |l| l == "en" || l.starts_with("en-"));
+
+        Some((
+            uri.as_str().to_string(),
+            definition.to_string(),
+            2 * position + usize::from(!english),
+        ))
+    }
+
+    /// Load URI-label pairs from all sources using the configured predicates.
+    /// Sources are named by their position in errors.
+    pub fn gather_terms(
+        &self,
+        readers: Vec<(impl BufRead, RdfFormat)>,
+    ) -> Result<Vec<Term>, IngestError> {
+        let mut terms = Vec::new();
+        for (i, (reader, format)) in readers.into_iter().enumerate() {
+            let (mut out, _) = self.gather_source(&format!("source #{}", i), reader, format)?;
+            terms.append(&mut out.terms);
+        }
+
+        Ok(terms)
+    }
+
+    pub fn build_from_readers(
+        &self,
+        readers: Vec<(impl BufRead, RdfFormat)>,
+    ) -> Result<TermMatcher, IngestError> {
+        let mut gathered = Gathered::default();
+        for (i, (reader, format)) in readers.into_iter().enumerate() {
+            let (out, _) = self.gather_source(&format!("source #{}", i), reader, format)?;
+            gathered.extend(out);
+        }
+
+        Ok(self.finish(gathered))
+    }
+
+    pub fn build_from_paths(&self, paths: Vec<&str>) -> Result<TermMatcher> {
+        Ok(self.build_with_report(paths)?.0)
+    }
+
+    /// Build a matcher from paths, along with a report for each source.
+    /// Errors are `IngestError`s locating the failing source.
+    pub fn build_with_report(&self, paths: Vec<&str>) -> Result<(TermMatcher, Vec<SourceReport>)> {
+        let mut gathered = Gathered::default();
+        let mut reports = Vec::new();
+        for path in paths {
+            let (out, report) = if is_sparql_source(path) {
+                self.gather_sparql(path)?
+            } else {
+                let format = self
+                    .source_format(path)
+                    .or_else(|| SourceFormat::from_path(path));
+                match format {
+                    Some(SourceFormat::Obo) => {
+                        let reader = get_reader(path).map_err(|e| IngestError::new(path, e))?;
+                        self.gather_obo(path, reader)?
+                    }
+                    Some(SourceFormat::Table(format)) => {
+                        let reader = get_reader(path).map_err(|e| IngestError::new(path, e))?;
+                        self.gather_table(path, reader, format)?
+                    }
+                    Some(SourceFormat::Rdf(format)) => {
+                        let (reader, format) = get_source_with_format(path, Some(format))
+                            .map_err(|e| IngestError::new(path, e))?;
+                        self.gather_source(path, reader, format)?
+                    }
+                    None => {
+                        let (reader, format) =
+                            get_source(path).map_err(|e| IngestError::new(path, e))?;
+                        self.gather_source(path, reader, format)?
+                    }
+                }
+            };
+            gathered.extend(out);
+            reports.push(report);
+        }
+
+        Ok((self.finish(gathered), reports))
+    }
+
+    // Build the matcher, configured prefixes overriding those of sources.
+    fn finish(&self, mut gathered: Gathered) -> TermMatcher {
+        gathered.prefixes.merge(self.prefixes.clone());
+        gathered.into_matcher().with_normalizer(self.normalizer)
+    }
+}
+
+// Convert a triple to a parent edge if it links two named nodes with a hierarchy predicate.
+fn extract_edge(quad: &Quad) -> Option<(String, String)> {
+    match (&quad.subject, &quad.object) {
+        (Subject::NamedNode(child), RdfTerm::NamedNode(parent))
+            if HIERARCHY_PREDICATES.contains(&quad.predicate.as_str()) =>
+        {
+            Some((child.as_str().to_string(), parent.as_str().to_string()))
+        }
+        _ => None,
+    }
+}
+
+// Convert a triple to a (uri, type) pair if it types a named node.
+fn extract_type(quad: &Quad) -> Option<(String, String)> {
+    match (&quad.subject, &quad.object) {
+        (Subject::NamedNode(uri), RdfTerm::NamedNode(rdf_type))
+            if quad.predicate.as_str() == RDF_TYPE =>
+        {
+            Some((uri.as_str().to_string(), rdf_type.as_str().to_string()))
+        }
+        _ => None,
+    }
+}
+
+// Record that a named node is deprecated (owl:deprecated true), or replaced
+// by another term (IAO:0100001, as an IRI or CURIE).
+fn extract_deprecation(quad: &Quad, deprecations: &mut Deprecations) {
+    let Subject::NamedNode(uri) = &quad.subject else {
+        return;
     };
+    match (quad.predicate.as_str(), &quad.object) {
+        (OWL_DEPRECATED, RdfTerm::Literal(value)) if matches!(value.value(), "true" | "1") => {
+            deprecations.deprecate(uri.as_str())
+        }
+        (IAO_REPLACED_BY, RdfTerm::NamedNode(by)) => {
+            deprecations.add_replacement(uri.as_str(), by.as_str())
+        }
+        (IAO_REPLACED_BY, RdfTerm::Literal(by)) => {
+            deprecations.add_replacement(uri.as_str(), &term_uri(by.value()))
+        }
+        _ => {}
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+/// Terms, definitions, deprecations, parent edges, types and prefixes gathered from sources.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gathered {
+    pub terms: Vec<Term>,
+    /// Definitions of terms, unless the builder has no definition predicates.
+    pub definitions: Definitions,
+    /// Deprecated terms, always gathered.
+    pub deprecations: Deprecations,
+    /// Empty unless the builder gathers the hierarchy.
+    pub hierarchy: Hierarchy,
+    /// Empty unless the builder gathers types.
+    pub types: TypeIndex,
+    /// Prefixes declared in sources.
+    pub prefixes: PrefixMap,
+}
+
+impl Gathered {
+    pub fn from_terms(terms: Vec<Term>) -> Self {
+        Gathered {
+            terms,
+            ..Default::default()
+        }
+    }
+
+    /// Append the terms, definitions, deprecations, edges, types and prefixes
+    /// of another source. Definitions of earlier sources are kept.
+    pub fn extend(&mut self, other: Gathered) {
+        self.terms.extend(other.terms);
+        self.definitions.merge(other.definitions);
+        self.deprecations.merge(other.deprecations);
+        self.hierarchy.merge(other.hierarchy);
+        self.types.merge(other.types);
+        self.prefixes.merge(other.prefixes);
+    }
+
+    pub fn into_matcher(self) -> TermMatcher {
+        TermMatcher::from_terms(self.terms)
+            .with_definitions(self.definitions)
+            .with_deprecations(self.deprecations)
+            .with_hierarchy(self.hierarchy)
+            .with_types(self.types)
+            .with_prefixes(self.prefixes)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
 pub struct TermMatcher {
     pub terms: Vec<Term>,
+    /// Normalizes labels and queries before matching.
+    normalizer: Normalizer,
+    /// Normalized labels, in the same order as terms, see `reindex` if terms are modified.
+    labels: Vec<String>,
+    /// Prunes terms which cannot match a query, built from normalized labels.
+    index: CharIndex,
+    /// Normalized labels by edit distance, to find labels despite typos in a query.
+    vocabulary: BkTree,
+    /// Positions of the terms of each URI, rebuilt when loading the matcher.
+    #[serde(skip)]
+    uris: UriIndex,
+    /// Definition of URIs, if gathered.
+    definitions: Definitions,
+    /// Words of definitions, rebuilt with the definitions and when loading the matcher.
+    #[serde(skip)]
+    texts: TextIndex,
+    /// Deprecated URIs, excluded from matches by default.
+    deprecations: Deprecations,
+    /// Parent edges between URIs, if gathered.
+    hierarchy: Hierarchy,
+    /// Children of URIs, rebuilt with the hierarchy and when loading the matcher.
+    #[serde(skip)]
+    children: ChildIndex,
+    /// Types of URIs, if gathered.
+    types: TypeIndex,
+    /// Prefixes used to compact and expand URIs.
+    prefixes: PrefixMap,
 }
 
 impl Add for TermMatcher {
@@ -53,48 +635,434 @@ impl Add for TermMatcher {
         let terms = self
             .terms
             .into_iter()
-            .chain(rhs.terms.into_iter())
+            .chain(rhs.terms)
             .collect::<HashSet<Term>>()
             .into_iter()
             .collect();
+        let mut definitions = self.definitions;
+        definitions.merge(rhs.definitions);
+        let mut deprecations = self.deprecations;
+        deprecations.merge(rhs.deprecations);
+        let mut hierarchy = self.hierarchy;
+        hierarchy.merge(rhs.hierarchy);
+        let mut types = self.types;
+        types.merge(rhs.types);
+        let mut prefixes = self.prefixes;
+        prefixes.merge(rhs.prefixes);
 
-        TermMatcher { terms }
+        TermMatcher::from_terms(terms)
+            .with_normalizer(self.normalizer)
+            .with_definitions(definitions)
+            .with_deprecations(deprecations)
+            .with_hierarchy(hierarchy)
+            .with_types(types)
+            .with_prefixes(prefixes)
     }
 }
 
 impl TermMatcher {
     pub fn new() -> Self {
-        TermMatcher { terms: Vec::new() }
+        TermMatcher::default()
+    }
+    /// Build a matcher and its index from a collection of terms.
+    pub fn from_terms(terms: Vec<Term>) -> Self {
+        let normalizer = Normalizer::default();
+        let labels: Vec<String> = terms
+            .iter()
+            .map(|t| normalizer.normalize(&t.label))
+            .collect();
+        let index = CharIndex::new(labels.iter().map(|l| l.as_str()));
+        let vocabulary = BkTree::new(labels.iter().map(|l| l.as_str()));
+        let uris = UriIndex::new(terms.iter().map(|t| t.uri.as_str()));
+        TermMatcher {
+            terms,
+            normalizer,
+            labels,
+            index,
+            vocabulary,
+            uris,
+            definitions: Definitions::default(),
+            texts: TextIndex::default(),
+            deprecations: Deprecations::default(),
+            hierarchy: Hierarchy::default(),
+            children: ChildIndex::default(),
+            types: TypeIndex::default(),
+            prefixes: PrefixMap::default(),
+        }
+    }
+    /// Replace the normalization of labels and queries, normalizing labels again.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        if normalizer != self.normalizer {
+            self.normalizer = normalizer;
+            self.reindex();
+            self.texts = self.text_index();
+        }
+        self
+    }
+    /// Normalization applied to labels and queries before matching.
+    pub fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+    /// Replace the definitions of URIs.
+    pub fn with_definitions(mut self, definitions: Definitions) -> Self {
+        self.definitions = definitions;
+        self.texts = self.text_index();
+        self
+    }
+    // Index of the normalized words of definitions.
+    fn text_index(&self) -> TextIndex {
+        let texts: Vec<(&str, String)> = self
+            .definitions
+            .iter()
+            .map(|(uri, text)| (uri, self.normalizer.normalize(text)))
+            .collect();
+        TextIndex::new(texts.iter().map(|(uri, text)| (*uri, text.as_str())))
+    }
+    /// Definitions of URIs, e.g. rdfs:comment or IAO:0000115.
+    pub fn definitions(&self) -> &Definitions {
+        &self.definitions
+    }
+    /// Replace the deprecated URIs.
+    pub fn with_deprecations(mut self, deprecations: Deprecations) -> Self {
+        self.deprecations = deprecations;
+        self
+    }
+    /// Deprecated URIs and their replacements.
+    pub fn deprecations(&self) -> &Deprecations {
+        &self.deprecations
+    }
+    /// Replace the parent edges between URIs.
+    pub fn with_hierarchy(mut self, hierarchy: Hierarchy) -> Self {
+        self.children = ChildIndex::new(&hierarchy);
+        self.hierarchy = hierarchy;
+        self
+    }
+    /// Parent edges between URIs, empty unless gathered by the builder.
+    pub fn hierarchy(&self) -> &Hierarchy {
+        &self.hierarchy
+    }
+    /// Replace the types of URIs.
+    pub fn with_types(mut self, types: TypeIndex) -> Self {
+        self.types = types;
+        self
+    }
+    /// Types of URIs, empty unless gathered by the builder.
+    pub fn types(&self) -> &TypeIndex {
+        &self.types
+    }
+    /// Replace the prefixes used to compact and expand URIs.
+    pub fn with_prefixes(mut self, prefixes: PrefixMap) -> Self {
+        self.prefixes = prefixes;
+        self
+    }
+    /// Prefixes declared in sources or configured in the builder.
+    pub fn prefixes(&self) -> &PrefixMap {
+        &self.prefixes
+    }
+    /// Compact a URI into a CURIE with the matcher's prefixes, if possible.
+    pub fn compact(&self, uri: &str) -> String {
+        self.prefixes.compact(uri)
+    }
+    /// URI of a term from an IRI or CURIE, expanded with the matcher's prefixes.
+    pub fn term_uri(&self, id: &str) -> String {
+        self.prefixes.term_uri(id)
+    }
+    /// Direct parents of a URI, as concepts. Parents without labels are
+    /// represented by their URI.
+    pub fn parents(&self, uri: &str) -> Vec<Concept> {
+        self.concepts(self.hierarchy.parents(uri).iter().map(|p| p.as_str()))
+    }
+    /// All ancestors of a URI as concepts, nearest first.
+    pub fn ancestors(&self, uri: &str) -> Vec<Concept> {
+        self.concepts(self.hierarchy.ancestors(uri).into_iter())
+    }
+    /// Ancestors following the first parent at each step, nearest first.
+    pub fn lineage(&self, uri: &str) -> Vec<Concept> {
+        self.concepts(self.hierarchy.lineage(uri).into_iter())
+    }
+    /// URIs of the roots and all their descendants.
+    pub fn subtree<'a>(&'a self, roots: &'a [String]) -> HashSet<&'a str> {
+        let mut subtree = HashSet::new();
+        for root in roots {
+            subtree.insert(root.as_str());
+            subtree.extend(self.children.descendants(root));
+        }
+        subtree
+    }
+    // Concepts of the input URIs, in the same order.
+    fn concepts<'a>(&self, uris: impl Iterator<Item = &'a str>) -> Vec<Concept> {
+        uris.map(|uri| match self.uri_terms(uri).as_slice() {
+            [] => Concept {
+                uri: uri.to_string(),
+                label: uri.to_string(),
+                alt_labels: Vec::new(),
+            },
+            terms => Concept::from_terms(terms),
+        })
+        .collect()
+    }
+    // Terms of a URI, in input order.
+    fn uri_terms(&self, uri: &str) -> Vec<&Term> {
+        // A stale index cannot be trusted, scan all terms instead
+        if self.uris.len() == self.terms.len() {
+            self.uris.get(uri).iter().map(|i| &self.terms[*i]).collect()
+        } else {
+            self.terms.iter().filter(|t| t.uri == uri).collect()
+        }
+    }
+    /// Terms (labels) of a URI or CURIE, empty if the URI is unknown.
+    /// CURIEs are expanded with the matcher's prefixes.
+    pub fn lookup_terms(&self, id: &str) -> Vec<&Term> {
+        self.uri_terms(&self.term_uri(id))
+    }
+    /// Concept of a URI or CURIE with all its labels, if the URI is known.
+    pub fn lookup(&self, id: &str) -> Option<Concept> {
+        match self.lookup_terms(id).as_slice() {
+            [] => None,
+            terms => Some(Concept::from_terms(terms)),
+        }
+    }
+    /// Rebuild the normalized labels and indexes, required after modifying `terms` directly.
+    pub fn reindex(&mut self) {
+        self.labels = self
+            .terms
+            .iter()
+            .map(|t| self.normalizer.normalize(&t.label))
+            .collect();
+        self.index = CharIndex::new(self.labels.iter().map(|l| l.as_str()));
+        self.vocabulary = BkTree::new(self.labels.iter().map(|l| l.as_str()));
+        self.uris = UriIndex::new(self.terms.iter().map(|t| t.uri.as_str()));
+    }
+    /// Configure term gathering (e.g. annotation predicates) before loading sources.
+    pub fn builder() -> TermMatcherBuilder {
+        TermMatcherBuilder::default()
     }
     pub fn add_term(&mut self, term: Term) {
+        let label = self.normalizer.normalize(&term.label);
+        self.index.push(&label);
+        self.vocabulary.push(&label);
+        self.labels.push(label);
+        self.uris.push(&term.uri);
         self.terms.push(term);
     }
+    // Normalized label of the term at position i, normalized again if labels are stale.
+    fn label(&self, i: usize) -> Cow<str> {
+        match self.labels.len() == self.terms.len() {
+            true => Cow::Borrowed(self.labels[i].as_str()),
+            false => Cow::Owned(self.normalizer.normalize(&self.terms[i].label)),
+        }
+    }
+    /// Raw score of each term for the query with the scorer, in the same order
+    /// as terms, or None if its label does not match. Labels are normalized
+    /// once, when building the matcher, and the query on each call.
+    pub fn scores(&self, query: &str, scorer: &dyn Scorer) -> Vec<Option<f64>> {
+        let query = self.normalizer.normalize(query);
+        let terms: Vec<(&Term, Cow<str>)> = self
+            .terms
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t, self.label(i)))
+            .collect();
+        let mut scores = vec![None; terms.len()];
+        for (i, _, score) in score_terms(&query, &terms, scorer) {
+            scores[i] = Some(score);
+        }
+        scores
+    }
+    /// Rank all terms by similarity to the query, best first, along with their
+    /// scores. Terms whose label does not match the query score 0.
     pub fn rank_terms(&self, query: &str) -> Vec<(&Term, f64)> {
-        rank_terms(query, self.terms.iter().collect())
+        self.rank_terms_with(query, &SearchOptions::default())
+    }
+    /// Rank all terms accepted by the search options, scoring those whose label
+    /// does not match the query 0. See `rank_top_terms` to only rank matches.
+    pub fn rank_terms_with(&self, query: &str, options: &SearchOptions) -> Vec<(&Term, f64)> {
+        let mut ranked = self.rank_top_terms(query, usize::MAX, options);
+        let matched: HashSet<&Term> = ranked.iter().map(|(t, _)| *t).collect();
+        let accepts = self.term_filter(options);
+        ranked.extend(
+            self.terms
+                .iter()
+                .filter(|t| !matched.contains(t) && accepts(t))
+                .map(|t| (t, 0.0)),
+        );
+        // Stable, so that ties keep their order
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+    /// Rank the top n terms accepted by the search options, along with their scores.
+    /// Terms whose label does not match the query are left out, and only the
+    /// top n terms are sorted.
+    pub fn rank_top_terms(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Vec<(&Term, f64)> {
+        self.rank_top_terms_by(query, n, options, &options.scorer)
+    }
+    /// Rank the top n terms accepted by the search options with a custom scorer,
+    /// instead of the built-in scorer of the options. The scorer is given the
+    /// normalized query and labels, see `with_normalizer`.
+    pub fn rank_top_terms_by(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+        scorer: &dyn Scorer,
+    ) -> Vec<(&Term, f64)> {
+        let query = self.normalizer.normalize(query);
+        let query = query.as_str();
+        // Stale labels and index cannot be trusted, normalize and score all terms instead
+        let stale = self.labels.len() != self.terms.len();
+        let use_index =
+            scorer.requires_all_chars() && !stale && self.index.len() == self.terms.len();
+        let query_mask = char_mask(query);
+        let accepts = self.term_filter(options);
+        // Terms whose definition matches are candidates too
+        let definitions = match &options.fields {
+            Some(weights) if weights.definition > 0.0 => self.texts.scores(query),
+            _ => HashMap::new(),
+        };
+        // So are labels within a few edits of the query, which scorers requiring
+        // all characters of the query would miss
+        let max_typos = options.typos.unwrap_or_else(|| max_typos(query));
+        let typos: HashMap<&str, f64> = match scorer.requires_all_chars() && max_typos > 0 {
+            true => self
+                .vocabulary
+                .find(query, max_typos)
+                .into_iter()
+                .map(|(label, distance)| {
+                    let len = query.chars().count().max(label.chars().count());
+                    (label, 1.0 - distance as f64 / len as f64)
+                })
+                .collect(),
+            false => HashMap::new(),
+        };
+        let terms: Vec<(&Term, Cow<str>)> = self
+            .terms
+            .iter()
+            .enumerate()
+            .filter(|(i, t)| {
+                !use_index
+                    || self.index.is_candidate(*i, query_mask)
+                    || definitions.contains_key(t.uri.as_str())
+                    || typos.contains_key(self.labels[*i].as_str())
+            })
+            .filter(|(_, t)| accepts(t))
+            .map(|(i, t)| (t, self.label(i)))
+            .collect();
+        let typo_scorer;
+        let scorer: &dyn Scorer = match typos.is_empty() {
+            true => scorer,
+            false => {
+                typo_scorer = TypoScorer { scorer, typos };
+                &typo_scorer
+            }
+        };
+        match &options.fields {
+            Some(weights) => rank_top_fields(query, &terms, n, weights, &definitions, scorer),
+            None => rank_top_labels(query, &terms, n, scorer),
+        }
+    }
+    // Whether terms are accepted by the language, scope, deprecation, subtree
+    // and type filters of the search options.
+    fn term_filter<'a>(&'a self, options: &'a SearchOptions) -> impl Fn(&Term) -> bool + 'a {
+        let roots: Option<Vec<String>> = options
+            .roots
+            .as_ref()
+            .map(|roots| roots.iter().map(|r| self.term_uri(r)).collect());
+        let subtree: Option<HashSet<String>> = roots.as_ref().map(|roots| {
+            self.subtree(roots)
+                .into_iter()
+                .map(|uri| uri.to_string())
+                .collect()
+        });
+        let types: Option<Vec<TypeFilter>> = options
+            .types
+            .as_ref()
+            .map(|filters| filters.iter().map(|f| f.expand(&self.prefixes)).collect());
+        move |t: &Term| {
+            options.accepts(t)
+                && (options.include_deprecated || !self.deprecations.is_deprecated(&t.uri))
+                && subtree
+                    .as_ref()
+                    .map_or(true, |s| s.contains(t.uri.as_str()))
+                && types
+                    .as_ref()
+                    .map_or(true, |filters| self.types.matches(&t.uri, filters))
+        }
     }
     pub fn top_terms(&self, query: &str, n: usize) -> Vec<&Term> {
-        self.rank_terms(query)
+        self.top_terms_with(query, n, &SearchOptions::default())
+    }
+    /// Top n terms accepted by the search options.
+    pub fn top_terms_with(&self, query: &str, n: usize, options: &SearchOptions) -> Vec<&Term> {
+        self.rank_top_terms(query, n, options)
             .into_iter()
-            .take(n)
             .map(|t| t.0)
             .collect()
     }
-    pub fn from_readers(readers: Vec<(impl BufRead, RdfFormat)>) -> Self {
-        let terms = gather_terms(readers).collect();
+    /// Rank concepts (URIs with all their labels) by their best matching label.
+    pub fn rank_concepts(&self, query: &str) -> Vec<ConceptMatch> {
+        self.rank_concepts_with(query, &SearchOptions::default())
+    }
+    /// Rank concepts accepted by the search options by their best matching label.
+    pub fn rank_concepts_with(&self, query: &str, options: &SearchOptions) -> Vec<ConceptMatch> {
+        self.top_concepts_with(query, usize::MAX, options)
+    }
+    /// Top n concepts accepted by the search options.
+    pub fn top_concepts_with(
+        &self,
+        query: &str,
+        n: usize,
+        options: &SearchOptions,
+    ) -> Vec<ConceptMatch> {
+        // Only keep the best matching label of each URI. URIs have several labels,
+        // so rank more labels until n URIs are found or all matches are ranked.
+        let mut limit = n;
+        let best: Vec<(&Term, f64)> = loop {
+            let ranked = self.rank_top_terms(query, limit, options);
+            let exhausted = ranked.len() < limit;
+            let mut seen = HashSet::new();
+            let best: Vec<(&Term, f64)> = ranked
+                .into_iter()
+                .filter(|(t, _)| seen.insert(t.uri.as_str()))
+                .take(n)
+                .collect();
+            if exhausted || best.len() == n {
+                break best;
+            }
+            limit = limit.saturating_mul(4);
+        };
 
-        TermMatcher { terms }
+        best.into_iter()
+            .map(|(term, score)| {
+                // Collect all accepted labels of the retained URI
+                let mut terms = self.uri_terms(&term.uri);
+                terms.retain(|t| options.accepts(t));
+                ConceptMatch {
+                    concept: Concept::from_terms(&terms),
+                    matched_label: term.label.clone(),
+                    score,
+                }
+            })
+            .collect()
+    }
+    pub fn from_readers(readers: Vec<(impl BufRead, RdfFormat)>) -> Result<Self, IngestError> {
+        TermMatcherBuilder::default().build_from_readers(readers)
     }
 
     pub fn from_paths(paths: Vec<&str>) -> Result<Self> {
-        let readers = paths.into_iter().map(|p| get_source(p).unwrap()).collect();
-        let terms: Vec<Term> = gather_terms(readers).collect();
-
-        Ok(TermMatcher { terms })
+        TermMatcherBuilder::default().build_from_paths(paths)
     }
 
     pub fn load(path: &Path) -> Result<Self> {
         let bytes = std::fs::read(path)?;
-        let matcher = postcard::from_bytes(&bytes)?;
+        let mut matcher: TermMatcher = postcard::from_bytes(&bytes)?;
+        matcher.uris = UriIndex::new(matcher.terms.iter().map(|t| t.uri.as_str()));
+        matcher.texts = matcher.text_index();
+        matcher.children = ChildIndex::new(&matcher.hierarchy);
 
         Ok(matcher)
     }
@@ -108,107 +1076,969 @@ impl TermMatcher {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, Hash, PartialEq)]
 pub struct Term {
+    /// Full IRI, without angle brackets. See `TermMatcher::compact` for CURIEs.
     pub uri: String,
     pub label: String,
+    /// Language tag of the label, if any.
+    pub lang: Option<String>,
+    /// Annotation predicate the label was taken from.
+    pub predicate: String,
+}
+
+impl Term {
+    /// Whether the label is in the input language. Language tags are
+    /// matched case-insensitively, and "en" also matches "en-GB".
+    /// Labels without a language tag match any language.
+    pub fn matches_lang(&self, lang: &str) -> bool {
+        match &self.lang {
+            None => true,
+            Some(tag) => {
+                let tag = tag.to_lowercase();
+                let lang = lang.to_lowercase();
+                tag == lang || tag.starts_with(&format!("{}-", lang))
+            }
+        }
+    }
+
+    /// Scope of the label if it is a synonym (oboInOwl predicates).
+    pub fn synonym_scope(&self) -> Option<SynonymScope> {
+        SynonymScope::from_predicate(&self.predicate)
+    }
 }
 
 impl fmt::Display for Term {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} ({})", self.label, self.uri)
+        match &self.lang {
+            Some(lang) => write!(f, "{}@{} ({})", self.label, lang, self.uri),
+            None => write!(f, "{} ({})", self.label, self.uri),
+        }
     }
 }
 
-/// Get an rdf reader along with its format from a path
-pub fn get_source(path: &str) -> Result<(Box<dyn BufRead>, RdfFormat)> {
-    let file_ext = path.split('.').last().unwrap();
-    let ext = match file_ext {
-        "owl" => "xml",
-        "rdf" => "xml",
-        _ => file_ext,
-    };
-    let format = RdfFormat::from_extension(ext).expect("Unkown file extension");
-    if let Ok(url) = Url::parse(path) {
-        // Handle URL
-        let client = Client::new();
-        let response = client.get(url).send()?.error_for_status()?;
-        let reader = BufReader::new(response);
-        Ok((Box::new(reader), format)) // Return boxed reader for URL
-    } else {
-        // Handle file path
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        Ok((Box::new(reader), format)) // Return boxed reader for file
+/// A URI along with all its labels.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Concept {
+    pub uri: String,
+    /// Primary label, preferably from skos:prefLabel or rdfs:label.
+    pub label: String,
+    /// All other distinct labels.
+    pub alt_labels: Vec<String>,
+}
+
+impl Concept {
+    /// Build a concept from the terms of a single URI.
+    /// Panics if terms is empty.
+    pub fn from_terms(terms: &[&Term]) -> Self {
+        let priority = |t: &Term| match t.predicate.as_str() {
+            SKOS_PREF_LABEL => 0,
+            RDFS_LABEL => 1,
+            _ => 2,
+        };
+        let primary = terms
+            .iter()
+            .min_by_key(|t| priority(t))
+            .expect("A concept requires at least one term");
+        let mut alt_labels: Vec<String> = Vec::new();
+        for term in terms {
+            if term.label != primary.label && !alt_labels.contains(&term.label) {
+                alt_labels.push(term.label.clone());
+            }
+        }
+
+        Concept {
+            uri: primary.uri.clone(),
+            label: primary.label.clone(),
+            alt_labels,
+        }
+    }
+}
+
+/// A concept ranked by its best matching label.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConceptMatch {
+    pub concept: Concept,
+    /// The label which matched the query best.
+    pub matched_label: String,
+    pub score: f64,
+}
+
+impl fmt::Display for ConceptMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.concept.label, self.concept.uri)?;
+        if self.matched_label != self.concept.label {
+            write!(f, " via \"{}\"", self.matched_label)?;
+        }
+        Ok(())
+    }
+}
+
+/// Restricts which terms are considered when ranking, and how they are ranked.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchOptions {
+    /// Only consider labels in this language (untagged labels are kept).
+    pub lang: Option<String>,
+    /// Only consider synonyms with these scopes (other labels are kept).
+    pub scopes: Option<Vec<SynonymScope>>,
+    /// Only consider terms under one of these URIs (included) in the hierarchy.
+    /// CURIEs are expanded with the prefixes of the matcher, see `TermMatcher::term_uri`.
+    pub roots: Option<Vec<String>>,
+    /// Only consider terms whose URI matches one of these type filters.
+    pub types: Option<Vec<TypeFilter>>,
+    /// Also consider deprecated terms, see `TermMatcher::deprecations`.
+    pub include_deprecated: bool,
+    /// Rank URIs by their labels, synonyms and definition with these weights,
+    /// instead of ranking each label on its own.
+    pub fields: Option<FieldWeights>,
+    /// Scores labels against the query, fzy by default.
+    pub scorer: ScorerKind,
+    /// Maximum number of edits (Levenshtein distance) between the query and
+    /// labels matched as typos, by query length if None, see `scorer::max_typos`.
+    /// Only used with scorers requiring all query characters, such as fzy.
+    pub typos: Option<usize>,
+}
+
+impl SearchOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_lang(mut self, lang: &str) -> Self {
+        self.lang = Some(lang.to_string());
+        self
+    }
+
+    /// Only consider synonyms with this scope, in addition to previous ones.
+    pub fn with_scope(mut self, scope: SynonymScope) -> Self {
+        self.scopes.get_or_insert_with(Vec::new).push(scope);
+        self
+    }
+
+    /// Only consider terms in the subtree rooted at this URI, in addition to previous roots.
+    /// Requires the hierarchy, see `TermMatcherBuilder::hierarchy`.
+    pub fn with_root(mut self, root: &str) -> Self {
+        self.roots
+            .get_or_insert_with(Vec::new)
+            .push(root.trim().to_string());
+        self
+    }
+
+    /// Only consider terms of this kind or type, in addition to previous ones.
+    /// Requires types, see `TermMatcherBuilder::types`.
+    pub fn with_type(mut self, filter: TypeFilter) -> Self {
+        self.types.get_or_insert_with(Vec::new).push(filter);
+        self
+    }
+
+    /// Also consider deprecated terms, which are excluded by default.
+    pub fn with_deprecated(mut self) -> Self {
+        self.include_deprecated = true;
+        self
+    }
+
+    /// Rank URIs by the weighted scores of their labels, synonyms and definition,
+    /// returning the best matching term of each URI with a score between 0 and 1.
+    /// This finds terms by words which only appear in their definition.
+    pub fn with_fields(mut self, weights: FieldWeights) -> Self {
+        self.fields = Some(weights);
+        self
+    }
+
+    /// Score labels with this scorer instead of fzy, e.g. to tolerate typos
+    /// (Levenshtein) or word reordering (token set ratio).
+    pub fn with_scorer(mut self, scorer: ScorerKind) -> Self {
+        self.scorer = scorer;
+        self
+    }
+
+    /// Also match labels within this number of edits of the query, whatever
+    /// its length. 0 disables typo tolerance.
+    pub fn with_typos(mut self, max_typos: usize) -> Self {
+        self.typos = Some(max_typos);
+        self
+    }
+
+    /// Whether a term passes all filters, except deprecation, roots and types
+    /// which depend on the matcher.
+    pub fn accepts(&self, term: &Term) -> bool {
+        self.lang
+            .as_ref()
+            .map_or(true, |lang| term.matches_lang(lang))
+            && self.scopes.as_ref().map_or(true, |scopes| {
+                term.synonym_scope().map_or(true, |s| scopes.contains(&s))
+            })
     }
 }
+
 /// Returns the input term vector sorted by match score (best first),
-/// along with the individual matching scores.
+/// along with the individual matching scores. Terms which do not match score 0.
 pub fn rank_terms<'a>(query: &str, terms: Vec<&'a Term>) -> Vec<(&'a Term, f64)> {
-    let mut ranked: Vec<(&Term, f64)> = terms
-        .into_iter()
-        .map(|t| {
-            (
-                t,
-                rff::match_and_score(query, &t.label.to_string())
-                    .and_then(|m| Some(m.1.to_owned()))
-                    .unwrap_or(0.0),
-            )
-        })
-        .collect();
-    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    let labels: Vec<(&Term, &str)> = terms.into_iter().map(|t| (t, t.label.as_str())).collect();
+    let mut scores = vec![0.0; labels.len()];
+    for (i, _, score) in score_terms(query, &labels, &ScorerKind::Fzy) {
+        scores[i] = score;
+    }
+    let mut ranked: Vec<(&Term, f64)> = labels.into_iter().map(|(t, _)| t).zip(scores).collect();
+    // Stable, so that ties keep their order
+    #[cfg(feature = "parallel")]
+    ranked.par_sort_by(|a, b| b.1.total_cmp(&a.1));
+    #[cfg(not(feature = "parallel"))]
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
 
     ranked
 }
 
-// Load URI-label pairs from all sources.
-pub fn gather_terms(readers: Vec<(impl BufRead, RdfFormat)>) -> impl Iterator<Item = Term> {
-    // NOTE: May want to use bulk loader for better performances
-    let mut terms = Vec::new();
-    for (reader, format) in readers {
-        let parser = RdfParser::from_format(format).for_reader(reader);
-        // Drop blank nodes and filter by common annotation properties
-        let mut out = parser
-            .map(|t| t.expect("Error parsing RDF"))
-            .filter(|t| if let Subject::NamedNode(_) = t.subject {true} else {false})
-            .filter(|t| ANNOTATIONS.contains(t.predicate.as_str()))
-            .map(|t| Term {
-                uri: t.subject.to_string(),
-                label: t.object.to_string().replace("\"", ""),
-            })
-            .collect();
-        terms.append(&mut out);
+/// Returns the n best matching terms sorted by match score (best first),
+/// along with their matching scores. Terms which do not match are dropped
+/// and only the top n terms are sorted. Ties are ordered by position in the input.
+pub fn rank_top_terms<'a>(query: &str, terms: Vec<&'a Term>, n: usize) -> Vec<(&'a Term, f64)> {
+    rank_top_terms_by(query, terms, n, &ScorerKind::Fzy)
+}
+
+/// Same as `rank_top_terms`, scoring terms with the input scorer instead of fzy.
+pub fn rank_top_terms_by<'a>(
+    query: &str,
+    terms: Vec<&'a Term>,
+    n: usize,
+    scorer: &dyn Scorer,
+) -> Vec<(&'a Term, f64)> {
+    let labels: Vec<(&Term, &str)> = terms.into_iter().map(|t| (t, t.label.as_str())).collect();
+    rank_top_labels(query, &labels, n, scorer)
+}
+
+// Rank the n best terms by the score of the label paired with each term,
+// e.g. its normalized label.
+fn rank_top_labels<'a, L: AsRef<str> + Sync>(
+    query: &str,
+    terms: &[(&'a Term, L)],
+    n: usize,
+    scorer: &dyn Scorer,
+) -> Vec<(&'a Term, f64)> {
+    let mut scored = score_terms(query, terms, scorer);
+    let by_score =
+        |a: &(usize, &Term, f64), b: &(usize, &Term, f64)| b.2.total_cmp(&a.2).then(a.0.cmp(&b.0));
+    if n < scored.len() {
+        // Partition around the nth best term, then drop the rest
+        scored.select_nth_unstable_by(n, by_score);
+        scored.truncate(n);
+    }
+    #[cfg(feature = "parallel")]
+    scored.par_sort_unstable_by(by_score);
+    #[cfg(not(feature = "parallel"))]
+    scored.sort_unstable_by(by_score);
+
+    scored.into_iter().map(|(_, t, s)| (t, s)).collect()
+}
+
+// Score terms whose paired label matches the query, keeping their input position.
+fn score_terms<'a, L: AsRef<str> + Sync>(
+    query: &str,
+    terms: &[(&'a Term, L)],
+    scorer: &dyn Scorer,
+) -> Vec<(usize, &'a Term, f64)> {
+    let score = |(i, (t, label)): (usize, &(&'a Term, L))| {
+        scorer.score(query, label.as_ref()).map(|s| (i, *t, s))
+    };
+    #[cfg(feature = "parallel")]
+    let scored = terms.par_iter().enumerate().filter_map(score).collect();
+    #[cfg(not(feature = "parallel"))]
+    let scored = terms.iter().enumerate().filter_map(score).collect();
+
+    scored
+}
+
+/// URI of a term, as in `Term::uri`, from an IRI with or without angle
+/// brackets, or an OBO CURIE such as "UBERON:0000061". Use
+/// `PrefixMap::term_uri` to expand other CURIEs.
+pub fn term_uri(id: &str) -> String {
+    let id = id.trim();
+    if let Some(iri) = id.strip_prefix('<').and_then(|id| id.strip_suffix('>')) {
+        return iri.to_string();
+    }
+    match split_curie(id) {
+        Some(_) => obo::expand_curie(id),
+        None => id.to_string(),
+    }
+}
+
+/// Maps a raw fzy score into the [0, 1] range, higher is better.
+/// Exact matches score 1 and non-matches 0. Other scores are divided by the
+/// query length, which bounds the score of a fully consecutive match.
+/// Matches with many gaps have a negative raw score and are clamped to 0.
+pub fn normalize_score(score: f64, query: &str) -> f64 {
+    let len = query.chars().count();
+    if score == f64::INFINITY {
+        1.0
+    } else if !score.is_finite() || len == 0 {
+        0.0
+    } else {
+        (score / len as f64).clamp(0.0, 1.0)
     }
+}
 
-    terms.into_iter()
+// Load URI-label pairs from all sources using the default annotation predicates.
+pub fn gather_terms(readers: Vec<(impl BufRead, RdfFormat)>) -> Result<Vec<Term>, IngestError> {
+    TermMatcherBuilder::default().gather_terms(readers)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile;
 
     #[test]
     fn matcher_from_source() {
-        let source = vec!["../data/test_schema.ttl"];
+        let source = vec!["../../data/test_schema.ttl"];
         let matcher = TermMatcher::from_paths(source).unwrap();
         assert_eq!(matcher.terms.len(), 11);
     }
 
     #[test]
     fn rank_terms() {
-        let source = vec!["../data/test_schema.ttl"];
+        let source = vec!["../../data/test_schema.ttl"];
         let matcher = TermMatcher::from_paths(source).unwrap();
         let query = "Person";
         let ranked = matcher.rank_terms(query);
         assert_eq!(ranked[0].0.label, "Person");
     }
 
+    #[test]
+    fn rank_with_scorer() {
+        let source = vec!["../../data/test_schema.ttl"];
+        let matcher = TermMatcher::from_paths(source).unwrap();
+        let query = "langauge programming";
+        assert!(matcher
+            .rank_top_terms(query, usize::MAX, &SearchOptions::default())
+            .is_empty());
+
+        let options = SearchOptions::new().with_scorer(ScorerKind::TokenSet);
+        let ranked = matcher.rank_top_terms(query, 2, &options);
+        assert_eq!(ranked[0].0.label, "programming language");
+        assert!(ranked[0].1 < 1.0);
+        // Scorers other than fzy do not skip labels lacking query characters
+        let options = SearchOptions::new().with_scorer(ScorerKind::DamerauLevenshtein);
+        let ranked = matcher.rank_top_terms("Persno", 1, &options);
+        assert_eq!(ranked[0].0.label, "Person");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let ttl = r#"
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            <http://example.org/a> rdfs:label "a" .
+            <http://example.org/b> rdfs:label "b" "c" .
+            <http://example.org/d> rdfs:label "d" .
+        "#;
+        let err = TermMatcher::builder()
+            .gather_source("test.ttl", ttl.as_bytes(), RdfFormat::Turtle)
+            .unwrap_err();
+        assert_eq!(err.source, "test.ttl");
+        assert_eq!(err.line, Some(4));
+        assert_eq!(
+            err.to_string(),
+            r#"test.ttl:4:51: "c" is not a valid subject or graph name"#
+        );
+
+        let (Gathered { terms, .. }, report) = TermMatcher::builder()
+            .lenient(true)
+            .gather_source("test.ttl", ttl.as_bytes(), RdfFormat::Turtle)
+            .unwrap();
+        assert!(terms.iter().any(|t| t.label == "d"));
+        assert_eq!(report.terms, terms.len());
+        assert!(report.errors > 0);
+        assert_eq!(report.first_error.unwrap().line, Some(4));
+
+        let err = TermMatcher::from_paths(vec!["missing.ttl"]).unwrap_err();
+        let err = err.downcast::<IngestError>().unwrap();
+        assert_eq!(err.source, "missing.ttl");
+        assert!(err.to_string().starts_with("missing.ttl: "));
+    }
+
+    #[test]
+    fn top_terms() {
+        let source = vec!["../../data/test_schema.ttl"];
+        let matcher = TermMatcher::from_paths(source).unwrap();
+        let options = SearchOptions::default();
+        for query in ["a", "e", "or", ""] {
+            let ranked = matcher.rank_top_terms(query, usize::MAX, &SearchOptions::default());
+            let top = matcher.rank_top_terms(query, 3, &options);
+            assert_eq!(top.len(), 3);
+            assert_eq!(top, ranked[..3]);
+        }
+        assert!(matcher.top_terms("a", 0).is_empty());
+        assert_eq!(matcher.top_terms("", 100).len(), matcher.terms.len());
+    }
+
+    #[test]
+    fn prune_candidates() {
+        let source = vec!["../../data/test_schema.ttl"];
+        let mut matcher = TermMatcher::from_paths(source).unwrap();
+        let ranked = matcher.rank_top_terms("kwd", usize::MAX, &SearchOptions::default());
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.label, "keywords");
+
+        // Stale index is ignored, and can be rebuilt
+        matcher.terms.push(Term {
+            uri: "http://example.org/kwd".to_string(),
+            label: "kwd".to_string(),
+            lang: None,
+            predicate: RDFS_LABEL.to_string(),
+        });
+        assert_eq!(
+            matcher
+                .rank_top_terms("kwd", usize::MAX, &SearchOptions::default())
+                .len(),
+            2
+        );
+        matcher.reindex();
+        assert_eq!(
+            matcher
+                .rank_top_terms("kwd", usize::MAX, &SearchOptions::default())
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn normalized_labels() {
+        let term = |uri: &str, label: &str| Term {
+            uri: uri.to_string(),
+            label: label.to_string(),
+            lang: None,
+            predicate: RDFS_LABEL.to_string(),
+        };
+        let matcher = TermMatcher::from_terms(vec![
+            term("http://example.org/a", "Schrödinger equation"),
+            term("http://example.org/b", "naïve B-cell"),
+            term("http://example.org/c", "TNF-α"),
+        ]);
+        // Original labels are returned
+        assert_eq!(
+            matcher.top_terms("schrodinger", 1)[0].label,
+            "Schrödinger equation"
+        );
+        assert_eq!(
+            matcher.top_terms("NAIVE b cell", 1)[0].label,
+            "naïve B-cell"
+        );
+        assert!(matcher
+            .rank_top_terms("tnf alpha", usize::MAX, &SearchOptions::default())
+            .is_empty());
+        let scores = matcher.scores("SCHRODINGER", &ScorerKind::Fzy);
+        assert!(scores[0].is_some());
+        assert_eq!(scores[1..], [None, None]);
+
+        let matcher = matcher.with_normalizer("default,greek".parse().unwrap());
+        assert_eq!(matcher.top_terms("tnf alpha", 1)[0].label, "TNF-α");
+        let matcher = matcher.with_normalizer(Normalizer::none());
+        assert!(matcher
+            .rank_top_terms("schrodinger", usize::MAX, &SearchOptions::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn typo_candidates() {
+        let term = |uri: &str, label: &str| Term {
+            uri: uri.to_string(),
+            label: label.to_string(),
+            lang: None,
+            predicate: RDFS_LABEL.to_string(),
+        };
+        let mut matcher = TermMatcher::from_terms(vec![
+            term("http://example.org/a", "Lymphocyte"),
+            term("http://example.org/b", "lymph node"),
+        ]);
+        let query = "lymphocite";
+        let ranked = matcher.rank_top_terms(query, usize::MAX, &SearchOptions::default());
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.label, "Lymphocyte");
+        assert!((normalize_score(ranked[0].1, query) - 0.9).abs() < 1e-9);
+        let options = SearchOptions::new().with_typos(0);
+        assert!(matcher
+            .rank_top_terms(query, usize::MAX, &options)
+            .is_empty());
+
+        matcher.add_term(term("http://example.org/c", "leukocyte"));
+        assert_eq!(matcher.top_terms("leukocite", 1)[0].label, "leukocyte");
+        // Labels starting with the query do not hide typos
+        matcher.add_term(term("http://example.org/d", "lymphocite marker"));
+        let ranked = matcher.rank_top_terms(query, usize::MAX, &SearchOptions::default());
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[1].0.label, "Lymphocyte");
+    }
+
+    #[test]
+    fn normalized_scores() {
+        let source = vec!["../../data/test_schema.ttl"];
+        let matcher = TermMatcher::from_paths(source).unwrap();
+        let query = "org";
+        let scores: Vec<f64> = matcher
+            .rank_top_terms(query, usize::MAX, &SearchOptions::default())
+            .into_iter()
+            .map(|(_, s)| normalize_score(s, query))
+            .collect();
+        assert!(scores.iter().all(|s| (0.0..=1.0).contains(s)));
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+        assert_eq!(normalize_score(f64::INFINITY, query), 1.0);
+        assert_eq!(normalize_score(0.0, query), 0.0);
+    }
+
     #[test]
     fn serde() {
-        let source = vec!["../data/test_schema.ttl"];
+        let source = vec!["../../data/test_schema.ttl"];
         let matcher = TermMatcher::from_paths(source).unwrap();
         let out = tempfile::NamedTempFile::new().unwrap();
-        let _ = matcher.dump(&out.path());
-        let loaded = TermMatcher::load(&out.path()).unwrap();
+        let _ = matcher.dump(out.path());
+        let loaded = TermMatcher::load(out.path()).unwrap();
         assert_eq!(matcher, loaded);
     }
+
+    #[test]
+    fn custom_predicates() {
+        let source = vec!["../../data/test_schema.ttl"];
+        let comment = "http://www.w3.org/2000/01/rdf-schema#comment";
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+
+        let added = TermMatcher::builder()
+            .with_predicate(comment)
+            .build_from_paths(source.clone())
+            .unwrap();
+        assert_eq!(added.terms.len(), 21);
+
+        let removed = TermMatcher::builder()
+            .without_predicate(label)
+            .build_from_paths(source.clone())
+            .unwrap();
+        assert!(removed.terms.is_empty());
+
+        let replaced = TermMatcher::builder()
+            .with_predicates([comment])
+            .build_from_paths(source)
+            .unwrap();
+        assert_eq!(replaced.terms.len(), 10);
+    }
+
+    #[test]
+    fn literal_lang() {
+        let ttl = r#"
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            <http://example.org/cat> rdfs:label "cat"@en, "Katze"@de, "the \"cat\"" .
+        "#;
+        let matcher = TermMatcher::from_readers(vec![(ttl.as_bytes(), RdfFormat::Turtle)]).unwrap();
+        assert_eq!(matcher.terms.len(), 3);
+        assert!(matcher
+            .terms
+            .iter()
+            .any(|t| t.label == "the \"cat\"" && t.lang.is_none()));
+        assert!(matcher
+            .terms
+            .iter()
+            .all(|t| t.predicate == "http://www.w3.org/2000/01/rdf-schema#label"));
+
+        let options = SearchOptions::new().with_lang("de");
+        let ranked = matcher.rank_top_terms("Katze", usize::MAX, &options);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.label, "Katze");
+        assert_eq!(ranked[0].0.lang.as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn obo_synonyms() {
+        let obo = r#"
+[Term]
+id: CL:0000738
+name: leukocyte
+synonym: "leucocyte" EXACT []
+synonym: "immune cell" BROAD []
+synonym: "white blood cell
+"#;
+        let builder = TermMatcher::builder().with_synonyms();
+        assert!(builder.gather_obo("test.obo", obo.as_bytes()).is_err());
+        let (Gathered { terms, .. }, report) = builder
+            .lenient(true)
+            .gather_obo("test.obo", obo.as_bytes())
+            .unwrap();
+        assert_eq!(report.errors, 1);
+        let error = report.first_error.unwrap();
+        assert_eq!(error.line, Some(7));
+        assert_eq!(
+            error.to_string(),
+            r#"test.obo:7: synonym has an unterminated quoted text: "white blood cell"#
+        );
+        assert_eq!(terms[0].uri, "http://purl.obolibrary.org/obo/CL_0000738");
+
+        let matcher = TermMatcher::from_terms(terms);
+        let options = SearchOptions::new().with_scope(SynonymScope::Exact);
+        let ranked = matcher.rank_top_terms("cyte", usize::MAX, &options);
+        assert_eq!(ranked.len(), 2);
+        assert!(matcher
+            .rank_top_terms("immune", usize::MAX, &options)
+            .is_empty());
+        assert_eq!(
+            matcher.rank_top_terms("immune", usize::MAX, &SearchOptions::default())[0]
+                .0
+                .synonym_scope(),
+            Some(SynonymScope::Broad)
+        );
+
+        // Synonyms are only gathered on demand
+        let (gathered, _) = TermMatcher::builder()
+            .lenient(true)
+            .gather_obo("test.obo", obo.as_bytes())
+            .unwrap();
+        assert_eq!(gathered.terms.len(), 1);
+    }
+
+    #[test]
+    fn rank_concepts() {
+        let ttl = r#"
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            @prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+            <http://example.org/wbc> rdfs:label "leukocyte" ;
+                skos:prefLabel "white blood cell" ;
+                skos:altLabel "leucocyte", "WBC" .
+            <http://example.org/rbc> rdfs:label "red blood cell" .
+        "#;
+        let matcher = TermMatcher::from_readers(vec![(ttl.as_bytes(), RdfFormat::Turtle)]).unwrap();
+        let ranked = matcher.rank_concepts("leuko");
+        assert_eq!(ranked.len(), 1);
+        let best = &ranked[0];
+        assert_eq!(best.concept.uri, "http://example.org/wbc");
+        assert_eq!(best.concept.label, "white blood cell");
+        assert_eq!(best.concept.alt_labels.len(), 3);
+        assert_eq!(best.matched_label, "leukocyte");
+        // Top concepts rank more labels when the best ones share a URI
+        let options = SearchOptions::default();
+        let top = matcher.top_concepts_with("l", 2, &options);
+        let all = matcher.rank_concepts_with("l", &options);
+        assert_eq!(all.len(), 2);
+        assert_eq!(top, all);
+    }
+
+    #[test]
+    fn hierarchy() {
+        let ttl = r#"
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            @prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+            <http://example.org/cell> rdfs:label "cell" .
+            <http://example.org/blood> rdfs:label "hematopoietic cell" ;
+                rdfs:subClassOf <http://example.org/cell> .
+            <http://example.org/wbc> rdfs:label "leukocyte" ;
+                rdfs:subClassOf <http://example.org/blood>, [ a rdfs:Class ] ;
+                skos:broader <http://example.org/immune> .
+        "#;
+        let readers = || vec![(ttl.as_bytes(), RdfFormat::Turtle)];
+        let matcher = TermMatcher::from_readers(readers()).unwrap();
+        assert!(matcher.hierarchy().is_empty());
+
+        let matcher = TermMatcher::builder()
+            .hierarchy(true)
+            .build_from_readers(readers())
+            .unwrap();
+        assert_eq!(matcher.terms.len(), 3);
+        let parents = matcher.parents("http://example.org/wbc");
+        assert_eq!(parents.len(), 2);
+        // Parents without labels fall back to their URI
+        assert_eq!(parents[1].label, "http://example.org/immune");
+        let ancestors = matcher.ancestors("http://example.org/wbc");
+        assert_eq!(ancestors[0].label, "hematopoietic cell");
+        assert_eq!(ancestors[2].label, "cell");
+        let lineage = matcher.lineage("http://example.org/wbc");
+        assert_eq!(
+            lineage.iter().map(|c| c.label.as_str()).collect::<Vec<_>>(),
+            vec!["hematopoietic cell", "cell"]
+        );
+
+        // Edges are persisted in the cache, and children rebuilt on load
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("matcher.bin");
+        matcher.dump(&path).unwrap();
+        let loaded = TermMatcher::load(&path).unwrap();
+        assert_eq!(loaded, matcher);
+        let roots = vec!["http://example.org/blood".to_string()];
+        assert!(loaded.subtree(&roots).contains("http://example.org/wbc"));
+    }
+
+    #[test]
+    fn subtree_filter() {
+        let ttl = r#"
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            <http://purl.obolibrary.org/obo/UBERON_0000061> rdfs:label "anatomical structure" .
+            <http://example.org/heart> rdfs:label "heart" ;
+                rdfs:subClassOf <http://purl.obolibrary.org/obo/UBERON_0000061> .
+            <http://example.org/atrium> rdfs:label "heart atrium" ;
+                rdfs:subClassOf <http://example.org/heart> .
+            <http://example.org/heartbeat> rdfs:label "heartbeat" .
+        "#;
+        let matcher = TermMatcher::builder()
+            .hierarchy(true)
+            .build_from_readers(vec![(ttl.as_bytes(), RdfFormat::Turtle)])
+            .unwrap();
+        assert_eq!(
+            matcher
+                .rank_top_terms("heart", usize::MAX, &SearchOptions::default())
+                .len(),
+            3
+        );
+
+        let options = SearchOptions::new().with_root("UBERON:0000061");
+        let ranked = matcher.rank_top_terms("heart", usize::MAX, &options);
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked.iter().all(|(t, _)| t.label != "heartbeat"));
+
+        let options = SearchOptions::new().with_root("http://example.org/heart");
+        let concepts = matcher.rank_concepts_with("heart", &options);
+        assert_eq!(concepts.len(), 2);
+        assert_eq!(
+            term_uri("<http://example.org/heart>"),
+            "http://example.org/heart"
+        );
+        assert_eq!(term_uri("urn:x:1"), "urn:x:1");
+    }
+
+    #[test]
+    fn lookup() {
+        let source = vec!["../../data/test_schema.ttl"];
+        let mut matcher = TermMatcher::from_paths(source).unwrap();
+        let concept = matcher.lookup("schema:Organization").unwrap();
+        assert_eq!(concept.uri, "http://schema.org/Organization");
+        assert_eq!(concept.label, "Organization");
+        assert_eq!(
+            matcher
+                .lookup_terms("<http://schema.org/Organization>")
+                .len(),
+            1
+        );
+        assert!(matcher.lookup("http://schema.org/Unknown").is_none());
+
+        // The URI index is rebuilt on load
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("matcher.bin");
+        matcher.dump(&path).unwrap();
+        assert_eq!(TermMatcher::load(&path).unwrap(), matcher);
+
+        // Terms modified directly are still found before reindexing
+        matcher.terms.push(Term {
+            uri: "http://schema.org/Organization".to_string(),
+            label: "Organisation".to_string(),
+            lang: Some("en-GB".to_string()),
+            predicate: SKOS_ALT_LABEL.to_string(),
+        });
+        assert_eq!(
+            matcher.lookup("schema:Organization").unwrap().alt_labels,
+            ["Organisation"]
+        );
+        matcher.reindex();
+        assert_eq!(matcher.lookup_terms("schema:Organization").len(), 2);
+    }
+
+    #[test]
+    fn prefixes() {
+        let source = vec!["../../data/test_schema.ttl"];
+        let matcher = TermMatcher::from_paths(source.clone()).unwrap();
+        let uri = "http://schema.org/Organization";
+        assert!(matcher.terms.iter().any(|t| t.uri == uri));
+        assert_eq!(matcher.compact(uri), "schema:Organization");
+        assert_eq!(matcher.term_uri("schema:Organization"), uri);
+        // The empty prefix is not used for CURIEs
+        assert_eq!(
+            matcher.compact("http://example.org/Repo"),
+            "http://example.org/Repo"
+        );
+
+        // Configured prefixes override those of sources
+        let prefixes = PrefixMap::from_iter([("sdo", "http://schema.org/")]);
+        let matcher = TermMatcher::builder()
+            .with_prefixes(prefixes)
+            .build_from_paths(source)
+            .unwrap();
+        assert_eq!(matcher.compact(uri), "sdo:Organization");
+        assert_eq!(matcher.prefixes().namespace("schema"), None);
+        let options = SearchOptions::new().with_root("sdo:Organization");
+        assert_eq!(
+            matcher
+                .rank_top_terms("organization", usize::MAX, &options)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn type_filter() {
+        let ttl = r#"
+            @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            @prefix schema: <http://schema.org/> .
+            schema:Person a rdfs:Class ; rdfs:label "Person" .
+            schema:person a rdf:Property ; rdfs:label "person" .
+            schema:Monday a schema:DayOfWeek ; rdfs:label "Monday" .
+        "#;
+        let readers = || vec![(ttl.as_bytes(), RdfFormat::Turtle)];
+        let matcher = TermMatcher::builder()
+            .types(true)
+            .build_from_readers(readers())
+            .unwrap();
+        assert_eq!(matcher.types().len(), 3);
+        assert_eq!(
+            matcher
+                .rank_top_terms("person", usize::MAX, &SearchOptions::default())
+                .len(),
+            2
+        );
+
+        let options = SearchOptions::new().with_type(TypeFilter::Kind(TermKind::Property));
+        let ranked = matcher.rank_top_terms("person", usize::MAX, &options);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.uri, "http://schema.org/person");
+
+        let options =
+            SearchOptions::new().with_type("http://schema.org/DayOfWeek".parse().unwrap());
+        assert_eq!(matcher.rank_top_terms("mon", usize::MAX, &options).len(), 1);
+        assert!(matcher
+            .rank_top_terms("person", usize::MAX, &options)
+            .is_empty());
+
+        // CURIEs of types are expanded with the prefixes declared in sources
+        let options = SearchOptions::new().with_type("schema:DayOfWeek".parse().unwrap());
+        let ranked = matcher.rank_top_terms("mon", usize::MAX, &options);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.uri, "http://schema.org/Monday");
+
+        // Types are only gathered on demand
+        let matcher = TermMatcher::from_readers(readers()).unwrap();
+        assert!(matcher.types().is_empty());
+    }
+
+    #[test]
+    fn deprecated_terms() {
+        let ttl = r#"
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            @prefix owl: <http://www.w3.org/2002/07/owl#> .
+            @prefix obo: <http://purl.obolibrary.org/obo/> .
+            obo:CL_1 rdfs:label "neuron" .
+            obo:CL_2 rdfs:label "nerve cell" ; owl:deprecated true ;
+                obo:IAO_0100001 obo:CL_1 .
+            obo:CL_3 rdfs:label "obsolete neural cell" .
+            obo:CL_4 rdfs:label "neuroglia" ; obo:IAO_0100001 "CL:5" .
+            obo:CL_7 rdfs:label "glial cell" ;
+                <http://www.w3.org/2004/02/skos/core#altLabel> "obsolete glia" .
+        "#;
+        let obo = "[Term]\nid: CL:6\nname: neural cell\nis_obsolete: true\nreplaced_by: CL:1\n";
+        let builder = TermMatcher::builder();
+        let (rdf, _) = builder
+            .gather_source("test.ttl", ttl.as_bytes(), RdfFormat::Turtle)
+            .unwrap();
+        let (mut gathered, _) = builder.gather_obo("test.obo", obo.as_bytes()).unwrap();
+        gathered.extend(rdf);
+        let matcher = gathered.into_matcher();
+        let deprecations = matcher.deprecations();
+        assert_eq!(deprecations.len(), 4);
+        assert_eq!(
+            deprecations.replaced_by("http://purl.obolibrary.org/obo/CL_2"),
+            ["http://purl.obolibrary.org/obo/CL_1"]
+        );
+        assert_eq!(
+            deprecations.replaced_by("http://purl.obolibrary.org/obo/CL_4"),
+            ["http://purl.obolibrary.org/obo/CL_5"]
+        );
+        assert_eq!(
+            deprecations.replaced_by("http://purl.obolibrary.org/obo/CL_6"),
+            ["http://purl.obolibrary.org/obo/CL_1"]
+        );
+        // Obsolete synonyms do not deprecate live terms
+        assert!(!deprecations.is_deprecated("http://purl.obolibrary.org/obo/CL_7"));
+
+        // Deprecated terms are excluded unless requested
+        let ranked = matcher.rank_top_terms("neur", usize::MAX, &SearchOptions::default());
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.label, "neuron");
+        let options = SearchOptions::new().with_deprecated();
+        assert_eq!(
+            matcher.rank_top_terms("neur", usize::MAX, &options).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn definitions() {
+        let ttl = r#"
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            @prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+            @prefix obo: <http://purl.obolibrary.org/obo/> .
+            obo:UBERON_1 rdfs:label "cortex" ; rdfs:comment "editor note" ;
+                obo:IAO_0000115 "Couche externe d'un organe."@fr ;
+                obo:IAO_0000115 "Outer layer of an organ."@en .
+            obo:UBERON_2 rdfs:label "cortex" ; skos:definition "Cerebral cortex." .
+        "#;
+        let obo = "[Term]\nid: UBERON:1\nname: cortex\ndef: \"Other layer.\" []\n";
+        let builder = TermMatcher::builder();
+        let (mut gathered, _) = builder
+            .gather_source("test.ttl", ttl.as_bytes(), RdfFormat::Turtle)
+            .unwrap();
+        let (obo, _) = builder.gather_obo("test.obo", obo.as_bytes()).unwrap();
+        gathered.extend(obo);
+        let matcher = gathered.into_matcher();
+        let definitions = matcher.definitions();
+        // IAO definitions in English come first, and earlier sources win
+        assert_eq!(
+            definitions.get("http://purl.obolibrary.org/obo/UBERON_1"),
+            Some("Outer layer of an organ.")
+        );
+        assert_eq!(
+            definitions.get("http://purl.obolibrary.org/obo/UBERON_2"),
+            Some("Cerebral cortex.")
+        );
+        // Definitions are neither labels nor gathered without predicates
+        assert_eq!(matcher.terms.len(), 3);
+        let (none, _) = TermMatcher::builder()
+            .with_definition_predicates(Vec::<String>::new())
+            .gather_source("test.ttl", ttl.as_bytes(), RdfFormat::Turtle)
+            .unwrap();
+        assert!(none.definitions.is_empty());
+    }
+
+    #[test]
+    fn field_ranking() {
+        let obo = r#"
+[Term]
+id: UBERON:1
+name: cortex
+synonym: "outer zone" RELATED []
+def: "Outer layer of an organ." []
+
+[Term]
+id: UBERON:2
+name: cerebral cortex
+def: "Grey matter of the cerebrum." []
+
+[Term]
+id: UBERON:3
+name: outer ear
+"#;
+        let builder = TermMatcher::builder().with_synonyms();
+        let (gathered, _) = builder.gather_obo("test.obo", obo.as_bytes()).unwrap();
+        let matcher = gathered.into_matcher();
+
+        // Words of definitions cannot be found by label
+        assert!(matcher
+            .rank_top_terms("grey matter", usize::MAX, &SearchOptions::default())
+            .is_empty());
+        let options = SearchOptions::new().with_fields(FieldWeights::default());
+        let ranked = matcher.rank_top_terms("grey matter", usize::MAX, &options);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.label, "cerebral cortex");
+
+        // Each URI is returned once with its best term, ranked by all its fields
+        let ranked = matcher.rank_top_terms("outer", usize::MAX, &options);
+        let labels: Vec<&str> = ranked.iter().map(|(t, _)| t.label.as_str()).collect();
+        assert_eq!(labels, ["outer zone", "outer ear"]);
+        assert!(ranked.iter().all(|(_, s)| *s > 0.0 && *s <= 1.0));
+        let concepts = matcher.top_concepts_with("outer", 1, &options);
+        assert_eq!(concepts[0].concept.label, "cortex");
+        assert_eq!(concepts[0].matched_label, "outer zone");
+
+        let labels_only = "synonyms=0,definition=0".parse().unwrap();
+        let options = SearchOptions::new().with_fields(labels_only);
+        let ranked = matcher.rank_top_terms("outer", usize::MAX, &options);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0.label, "outer ear");
+    }
 }
@@ -1,9 +1,16 @@
-use fuzon::ui::{interactive, search};
+use fuzon::ui::{
+    deprecation_note, format_concept, format_term, format_uri, interactive, search,
+    search_concepts, ResultStyle,
+};
 use std::fs;
 
-use anyhow::Result;
-use clap::Parser;
-use fuzon::{cache::get_cache_path, TermMatcher};
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+use fuzon::{
+    cache::get_cache_path_with, get_reader, parse_source_format, table::TableMapping, FieldWeights,
+    Normalizer, PrefixMap, ScorerKind, SearchOptions, SourceFormat, SynonymScope, TermMatcher,
+    TermMatcherBuilder, TypeFilter,
+};
 
 /// fuzzy match terms from ontologies to get their uri
 #[derive(Parser, Debug)]
@@ -20,43 +27,287 @@ struct Args {
     #[clap(short, long)]
     top: Option<usize>,
 
+    /// Only match labels in this language (e.g. "en"). Labels without language tag are kept.
+    #[clap(short, long)]
+    lang: Option<String>,
+
+    /// Group labels by URI and show one result per concept.
+    #[clap(short, long, default_value = "false")]
+    concepts: bool,
+
     /// Do not load from cache.
     #[clap(short, long, default_value = "false")]
     no_cache: bool,
+
+    /// Annotation predicate (full IRI) to gather labels from, in addition to the defaults.
+    #[clap(short, long)]
+    predicate: Vec<String>,
+
+    /// Also gather synonyms (oboInOwl exact, broad, narrow and related synonyms).
+    #[clap(long, default_value = "false")]
+    synonyms: bool,
+
+    /// Also gather parent classes (rdfs:subClassOf, skos:broader, OBO is_a).
+    /// The interactive mode then shows the lineage of the selected result.
+    #[clap(long, default_value = "false")]
+    hierarchy: bool,
+
+    /// Only match terms under this class or concept (included), given as an IRI or OBO CURIE
+    /// (e.g. UBERON:0000061). Implies --hierarchy.
+    #[clap(long)]
+    root: Vec<String>,
+
+    /// Only match terms of this kind (class, property or individual) or rdf:type (IRI or CURIE).
+    /// Implies gathering types.
+    #[clap(long = "type")]
+    types: Vec<TypeFilter>,
+
+    /// Also match deprecated and obsolete terms, showing their replacement if any.
+    #[clap(long, default_value = "false")]
+    include_deprecated: bool,
+
+    /// Rank URIs by their labels, synonyms and definition instead of each label on its own,
+    /// to also find terms by words of their definition. Fields can be weighted, e.g.
+    /// "label=1,synonyms=0.7,definition=0.5" (the defaults).
+    #[clap(long, num_args = 0..=1, default_missing_value = "default")]
+    fields: Option<FieldWeights>,
+
+    /// Scorer used to match labels: fzy (default, for autocompletion), jaro-winkler,
+    /// levenshtein, damerau-levenshtein (typos) or token-set (word order).
+    #[clap(long, default_value = "fzy")]
+    scorer: ScorerKind,
+
+    /// Maximum number of edits between the query and labels matched as typos with fzy,
+    /// e.g. "lymphocite" for "lymphocyte". Defaults to 0, 1 or 2 by query length.
+    #[clap(long)]
+    typos: Option<usize>,
+
+    /// Normalization of labels and queries before matching, as comma-separated steps:
+    /// diacritics (NFKD and accent folding), case, punctuation, greek (spell out Greek
+    /// letters), "default" for the first three, or "none".
+    #[clap(long, default_value = "default")]
+    normalize: Normalizer,
+
+    /// Show URIs as CURIEs (e.g. CL:0000738), using the prefixes declared in sources,
+    /// those given with --prefixes, and the OBO convention.
+    #[clap(long, default_value = "false")]
+    curie: bool,
+
+    /// JSON-LD context or prefix map (JSON object or bioregistry records) used to
+    /// compact and expand URIs. Can be a file path or a URL.
+    #[clap(long)]
+    prefixes: Vec<String>,
+
+    /// Only match synonyms with this scope (exact, broad, narrow or related). Other labels are kept.
+    #[clap(long)]
+    scope: Vec<SynonymScope>,
+
+    /// Annotation predicate (full IRI) to remove from the defaults.
+    #[clap(long)]
+    exclude_predicate: Vec<String>,
+
+    /// Only use predicates given with --predicate, ignoring the defaults.
+    #[clap(long, default_value = "false")]
+    only_predicates: bool,
+
+    /// Predicate (full IRI) of definitions shown along with results, replacing the defaults
+    /// (IAO:0000115, skos:definition, rdfs:comment). The first one found for a term is used.
+    #[clap(long)]
+    definition_predicate: Vec<String>,
+
+    /// Skip invalid triples instead of aborting, and report how many were skipped.
+    #[clap(long, default_value = "false")]
+    lenient: bool,
+
+    /// Format of all sources, as an extension (e.g. "ttl", "owl", "csv") or media type.
+    /// By default, it is detected from the extension, Content-Type or content.
+    #[clap(short, long, value_parser = parse_format_arg)]
+    format: Option<SourceFormat>,
+
+    /// Column of term identifiers in tabular sources (CSV, TSV, JSON lines).
+    #[clap(long, default_value = "id")]
+    id_column: String,
+
+    /// Column of labels in tabular sources.
+    #[clap(long, default_value = "label")]
+    label_column: String,
+
+    /// Column of synonyms in tabular sources.
+    #[clap(long, default_value = "synonyms")]
+    synonyms_column: String,
+
+    /// Separator between synonyms in tabular sources.
+    #[clap(long, default_value = "|")]
+    synonym_separator: String,
+
+    /// Template for URIs of terms in tabular sources, e.g. "https://example.org/{id}".
+    /// Templates without {id} are used as a prefix.
+    #[clap(long)]
+    uri_template: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show the labels and definition of URIs or CURIEs, failing if any of them is unknown.
+    Lookup {
+        /// URIs or CURIEs to look up, e.g. CL:0000738.
+        #[clap(required = true)]
+        ids: Vec<String>,
+    },
+}
+
+fn parse_format_arg(name: &str) -> Result<SourceFormat, String> {
+    parse_source_format(name).ok_or_else(|| format!("unknown format: {}", name))
+}
+
+// Print the labels and definition of each URI or CURIE, and fail if any is unknown.
+fn lookup(matcher: &TermMatcher, ids: &[String], curie: bool) -> Result<()> {
+    let mut unknown = Vec::new();
+    for id in ids {
+        let Some(concept) = matcher.lookup(id) else {
+            unknown.push(id.as_str());
+            continue;
+        };
+        let mut line = format!(
+            "{} ({})",
+            concept.label,
+            format_uri(matcher, &concept.uri, curie)
+        );
+        if !concept.alt_labels.is_empty() {
+            line.push_str(&format!(" [alt: {}]", concept.alt_labels.join(", ")));
+        }
+        println!("{}{}", line, deprecation_note(matcher, &concept.uri, curie));
+        if let Some(definition) = matcher.definitions().get(&concept.uri) {
+            println!("  {}", definition);
+        }
+    }
+    if !unknown.is_empty() {
+        bail!("Unknown URIs: {}", unknown.join(", "));
+    }
+
+    Ok(())
+}
+
+// Build the matcher from sources, warning about skipped triples.
+fn build(builder: &TermMatcherBuilder, sources: Vec<&str>) -> Result<TermMatcher> {
+    let (matcher, reports) = builder.build_with_report(sources)?;
+    for report in reports.iter().filter(|r| r.errors > 0) {
+        eprintln!("Warning: {}", report);
+    }
+
+    Ok(matcher)
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    let mut sources = args.source.iter().map(|s| s.as_str()).collect();
+    let mut sources: Vec<&str> = args.source.iter().map(|s| s.as_str()).collect();
 
-    // Attempt to load from cache
-    let matcher: TermMatcher;
-    if !args.no_cache {
-        let cache_path = get_cache_path(&mut sources)?;
+    let mut builder = TermMatcher::builder();
+    if args.only_predicates {
+        builder = builder.with_predicates(&args.predicate);
+    }
+    for predicate in &args.predicate {
+        builder = builder.with_predicate(predicate);
+    }
+    if args.synonyms {
+        builder = builder.with_synonyms();
+    }
+    for predicate in &args.exclude_predicate {
+        builder = builder.without_predicate(predicate);
+    }
+    if !args.definition_predicate.is_empty() {
+        builder = builder.with_definition_predicates(&args.definition_predicate);
+    }
+    builder = builder
+        .lenient(args.lenient)
+        .hierarchy(args.hierarchy || !args.root.is_empty())
+        .types(!args.types.is_empty())
+        .with_normalizer(args.normalize);
+    for path in &args.prefixes {
+        let reader = get_reader(path)?;
+        let prefixes = PrefixMap::from_reader(reader).map_err(|e| anyhow!("{}: {}", path, e))?;
+        builder = builder.with_prefixes(prefixes);
+    }
+    if let Some(format) = args.format {
+        builder = builder.with_format(format);
+    }
+    builder = builder.with_table_mapping(TableMapping {
+        id: args.id_column,
+        label: args.label_column,
+        synonyms: Some(args.synonyms_column),
+        separator: args.synonym_separator,
+        uri_template: args.uri_template,
+    });
+
+    // Attempt to load from cache, unless sources cannot be stamped (e.g. SPARQL
+    // endpoints without modification date)
+    let cache_path = match args.no_cache {
+        false => match get_cache_path_with(&mut sources, &builder) {
+            Ok(path) => Some(path),
+            Err(err) => {
+                eprintln!("Warning: not caching sources: {}", err);
+                None
+            }
+        },
+        true => None,
+    };
+    let matcher = if let Some(cache_path) = cache_path {
         let _ = fs::create_dir_all(cache_path.parent().unwrap());
         // Cache hit
-        matcher = if let Ok(matcher) = TermMatcher::load(&cache_path) {
+        if let Ok(matcher) = TermMatcher::load(&cache_path) {
             matcher
         // Cache miss
         } else {
-            let matcher = TermMatcher::from_paths(sources)?;
+            let matcher = build(&builder, sources)?;
             matcher.dump(&cache_path)?;
             matcher
-        };
+        }
     } else {
-        matcher = TermMatcher::from_paths(sources)?;
-    }
+        build(&builder, sources)?
+    };
 
+    let options = SearchOptions {
+        lang: args.lang,
+        scopes: (!args.scope.is_empty()).then_some(args.scope),
+        roots: (!args.root.is_empty()).then_some(args.root),
+        types: (!args.types.is_empty()).then_some(args.types),
+        include_deprecated: args.include_deprecated,
+        fields: args.fields,
+        scorer: args.scorer,
+        typos: args.typos,
+    };
+
+    let style = ResultStyle {
+        concepts: args.concepts,
+        curie: args.curie,
+    };
+
+    // Look up URIs
+    if let Some(Command::Lookup { ids }) = &args.command {
+        lookup(&matcher, ids, style.curie)
     // Search for query
-    if let Some(query) = args.query {
-        for (term, score) in search(&matcher, &query, args.top) {
-            println!("[{}] {}", score, term)
+    } else if let Some(query) = args.query {
+        if style.concepts {
+            for result in search_concepts(&matcher, &query, args.top, &options) {
+                println!(
+                    "[{}] {}",
+                    result.score,
+                    format_concept(&matcher, &result, style.curie)
+                )
+            }
+        } else {
+            for (term, score) in search(&matcher, &query, args.top, &options) {
+                println!("[{}] {}", score, format_term(&matcher, term, style.curie))
+            }
         }
         Ok(())
     // Or interactively trigger search on keystrokes
     } else {
-        interactive(&matcher, args.top)
+        interactive(&matcher, args.top, &options, style)
     }
 }
 
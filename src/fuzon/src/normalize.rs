@@ -0,0 +1,204 @@
+use core::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+// Greek letters (lowercase, uppercase) and their spelling.
+const GREEK_LETTERS: [(char, char, &str); 24] = [
+    ('α', 'Α', "alpha"),
+    ('β', 'Β', "beta"),
+    ('γ', 'Γ', "gamma"),
+    ('δ', 'Δ', "delta"),
+    ('ε', 'Ε', "epsilon"),
+    ('ζ', 'Ζ', "zeta"),
+    ('η', 'Η', "eta"),
+    ('θ', 'Θ', "theta"),
+    ('ι', 'Ι', "iota"),
+    ('κ', 'Κ', "kappa"),
+    ('λ', 'Λ', "lambda"),
+    ('μ', 'Μ', "mu"),
+    ('ν', 'Ν', "nu"),
+    ('ξ', 'Ξ', "xi"),
+    ('ο', 'Ο', "omicron"),
+    ('π', 'Π', "pi"),
+    ('ρ', 'Ρ', "rho"),
+    ('σ', 'Σ', "sigma"),
+    ('τ', 'Τ', "tau"),
+    ('υ', 'Υ', "upsilon"),
+    ('φ', 'Φ', "phi"),
+    ('χ', 'Χ', "chi"),
+    ('ψ', 'Ψ', "psi"),
+    ('ω', 'Ω', "omega"),
+];
+
+// Spelling of a Greek letter, and whether it is uppercase.
+fn greek_name(c: char) -> Option<(&'static str, bool)> {
+    if c == 'ς' {
+        return Some(("sigma", false));
+    }
+    GREEK_LETTERS
+        .iter()
+        .find(|(lower, upper, _)| c == *lower || c == *upper)
+        .map(|(_, upper, name)| (*name, c == *upper))
+}
+
+/// Steps applied to labels and queries before matching, so that e.g.
+/// "naïve B-cell" matches "naive b cell". Labels are normalized once when
+/// building the matcher, and results show the original labels.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, Hash, PartialEq)]
+pub struct Normalizer {
+    /// Decompose characters (NFKD), e.g. full-width letters or ligatures,
+    /// and drop diacritics.
+    pub diacritics: bool,
+    /// Lowercase letters.
+    pub case: bool,
+    /// Replace runs of whitespace and punctuation with a single space, and trim.
+    pub punctuation: bool,
+    /// Spell out Greek letters, so that "α" and "alpha" match each other.
+    pub greek: bool,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Normalizer {
+            diacritics: true,
+            case: true,
+            punctuation: true,
+            greek: false,
+        }
+    }
+}
+
+impl Normalizer {
+    /// Normalizer leaving texts unchanged.
+    pub fn none() -> Self {
+        Normalizer {
+            diacritics: false,
+            case: false,
+            punctuation: false,
+            greek: false,
+        }
+    }
+
+    /// Apply the enabled steps to a text.
+    pub fn normalize(&self, text: &str) -> String {
+        let decomposed: String = if self.diacritics {
+            text.nfkd().filter(|c| !is_combining_mark(*c)).collect()
+        } else {
+            text.to_string()
+        };
+        let mut normalized = String::with_capacity(decomposed.len());
+        // Whether a separator is pending, dropped at the start and end
+        let mut separator = false;
+        for c in decomposed.chars() {
+            if self.punctuation && !c.is_alphanumeric() {
+                separator = !normalized.is_empty();
+                continue;
+            }
+            if separator {
+                normalized.push(' ');
+                separator = false;
+            }
+            match greek_name(c).filter(|_| self.greek) {
+                Some((name, true)) if !self.case => {
+                    normalized.push_str(&name[..1].to_uppercase());
+                    normalized.push_str(&name[1..]);
+                }
+                Some((name, _)) => normalized.push_str(name),
+                None if self.case => normalized.extend(c.to_lowercase()),
+                None => normalized.push(c),
+            }
+        }
+        normalized
+    }
+
+    // Names of the enabled steps.
+    fn steps(&self) -> Vec<&'static str> {
+        [
+            (self.diacritics, "diacritics"),
+            (self.case, "case"),
+            (self.punctuation, "punctuation"),
+            (self.greek, "greek"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
+    }
+}
+
+impl FromStr for Normalizer {
+    type Err = String;
+
+    /// Parse comma-separated steps ("diacritics", "case", "punctuation",
+    /// "greek"), where "default" stands for the default steps, e.g.
+    /// "default,greek". "" gives the defaults and "none" disables all steps.
+    fn from_str(steps: &str) -> Result<Self, Self::Err> {
+        let steps = steps.trim();
+        if steps.is_empty() {
+            return Ok(Normalizer::default());
+        }
+        let mut normalizer = Normalizer::none();
+        for step in steps.split(',') {
+            match step.trim().to_lowercase().as_str() {
+                "default" => {
+                    let default = Normalizer::default();
+                    normalizer.diacritics |= default.diacritics;
+                    normalizer.case |= default.case;
+                    normalizer.punctuation |= default.punctuation;
+                    normalizer.greek |= default.greek;
+                }
+                "none" => {}
+                "diacritics" => normalizer.diacritics = true,
+                "case" => normalizer.case = true,
+                "punctuation" => normalizer.punctuation = true,
+                "greek" => normalizer.greek = true,
+                _ => return Err(format!("unknown normalization step: {}", step)),
+            }
+        }
+
+        Ok(normalizer)
+    }
+}
+
+impl fmt::Display for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.steps().as_slice() {
+            [] => write!(f, "none"),
+            steps => write!(f, "{}", steps.join(",")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        let normalizer = Normalizer::default();
+        assert_eq!(normalizer.normalize("Schrödinger"), "schrodinger");
+        assert_eq!(
+            normalizer.normalize("  naïve B-cell, (CD4+)"),
+            "naive b cell cd4"
+        );
+        assert_eq!(normalizer.normalize("ＦＵＬＬ　ｗｉｄｔｈ"), "full width");
+        assert_eq!(normalizer.normalize("TNF-α"), "tnf α");
+
+        let greek: Normalizer = "default,greek".parse().unwrap();
+        assert_eq!(greek.normalize("TNF-α"), "tnf alpha");
+        assert_eq!(greek.normalize("Ωmega ς"), "omegamega sigma");
+        let cased = Normalizer {
+            case: false,
+            ..greek
+        };
+        assert_eq!(cased.normalize("Δ-Notch"), "Delta Notch");
+        assert_eq!(Normalizer::none().normalize(" Naïve "), " Naïve ");
+
+        assert_eq!("".parse(), Ok(Normalizer::default()));
+        assert_eq!("none".parse(), Ok(Normalizer::none()));
+        assert_eq!(greek.to_string().parse(), Ok(greek));
+        assert_eq!(Normalizer::none().to_string(), "none");
+        assert!("stemming".parse::<Normalizer>().is_err());
+    }
+}
@@ -0,0 +1,514 @@
+use core::fmt;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{BufRead, Lines},
+    str::FromStr,
+};
+
+use oxrdf::{GraphName, Literal, NamedNode, Quad, Term as RdfTerm};
+
+use crate::{
+    definition::{IAO_DEFINITION, RDFS_COMMENT},
+    deprecation::{IAO_REPLACED_BY, OWL_DEPRECATED},
+    hierarchy::RDFS_SUBCLASS_OF,
+    types::{OWL_CLASS, RDF_TYPE},
+    RDFS_LABEL,
+};
+
+const OBO_PURL: &str = "http://purl.obolibrary.org/obo/";
+pub(crate) const OBO_EXACT_SYNONYM: &str =
+    "http://www.geneontology.org/formats/oboInOwl#hasExactSynonym";
+pub(crate) const OBO_BROAD_SYNONYM: &str =
+    "http://www.geneontology.org/formats/oboInOwl#hasBroadSynonym";
+pub(crate) const OBO_NARROW_SYNONYM: &str =
+    "http://www.geneontology.org/formats/oboInOwl#hasNarrowSynonym";
+pub(crate) const OBO_RELATED_SYNONYM: &str =
+    "http://www.geneontology.org/formats/oboInOwl#hasRelatedSynonym";
+
+/// Scope of a synonym, as defined by OBO and oboInOwl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SynonymScope {
+    Exact,
+    Broad,
+    Narrow,
+    Related,
+}
+
+impl SynonymScope {
+    pub const ALL: [SynonymScope; 4] = [
+        SynonymScope::Exact,
+        SynonymScope::Broad,
+        SynonymScope::Narrow,
+        SynonymScope::Related,
+    ];
+
+    /// oboInOwl annotation predicate of synonyms with this scope.
+    pub fn predicate(&self) -> &'static str {
+        match self {
+            SynonymScope::Exact => OBO_EXACT_SYNONYM,
+            SynonymScope::Broad => OBO_BROAD_SYNONYM,
+            SynonymScope::Narrow => OBO_NARROW_SYNONYM,
+            SynonymScope::Related => OBO_RELATED_SYNONYM,
+        }
+    }
+
+    /// Scope of synonyms annotated with an oboInOwl predicate, if any.
+    pub fn from_predicate(predicate: &str) -> Option<Self> {
+        match predicate {
+            OBO_EXACT_SYNONYM => Some(SynonymScope::Exact),
+            OBO_BROAD_SYNONYM => Some(SynonymScope::Broad),
+            OBO_NARROW_SYNONYM => Some(SynonymScope::Narrow),
+            OBO_RELATED_SYNONYM => Some(SynonymScope::Related),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for SynonymScope {
+    type Err = String;
+
+    /// Parse a scope name case-insensitively, e.g. "EXACT" or "exact".
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_uppercase().as_str() {
+            "EXACT" => Ok(SynonymScope::Exact),
+            "BROAD" => Ok(SynonymScope::Broad),
+            "NARROW" => Ok(SynonymScope::Narrow),
+            "RELATED" => Ok(SynonymScope::Related),
+            _ => Err(format!("unknown synonym scope: {}", name)),
+        }
+    }
+}
+
+impl fmt::Display for SynonymScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SynonymScope::Exact => "EXACT",
+            SynonymScope::Broad => "BROAD",
+            SynonymScope::Narrow => "NARROW",
+            SynonymScope::Related => "RELATED",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Expand an OBO identifier to an IRI. Prefixed identifiers (CURIEs) use the
+/// OBO PURL, e.g. "CL:0000738" becomes "http://purl.obolibrary.org/obo/CL_0000738".
+/// Identifiers which are already IRIs are kept as is.
+pub fn expand_curie(id: &str) -> String {
+    expand_id(id, &HashMap::new(), None)
+}
+
+/// Compact an OBO PURL into a CURIE, e.g. "http://purl.obolibrary.org/obo/CL_0000738"
+/// becomes "CL:0000738". Other IRIs are not compacted.
+pub fn compact_iri(iri: &str) -> Option<String> {
+    let (prefix, local) = iri.strip_prefix(OBO_PURL)?.split_once('_')?;
+    let valid = prefix.starts_with(|c: char| c.is_ascii_alphabetic())
+        && prefix.chars().all(|c| c.is_ascii_alphanumeric())
+        && !local.is_empty()
+        && !local.contains(['/', '#']);
+
+    valid.then(|| format!("{}:{}", prefix, local))
+}
+
+// Expand an identifier using the idspaces and ontology declared in the header.
+fn expand_id(id: &str, idspaces: &HashMap<String, String>, ontology: Option<&str>) -> String {
+    if id.contains("://") {
+        return id.to_string();
+    }
+    match id.split_once(':') {
+        Some((prefix, local)) => match idspaces.get(prefix) {
+            Some(base) => format!("{}{}", base, local),
+            None => format!("{}{}_{}", OBO_PURL, prefix, local),
+        },
+        // Unprefixed identifiers are local to the ontology
+        None => format!("{}{}#{}", OBO_PURL, ontology.unwrap_or_default(), id),
+    }
+}
+
+/// Failure while parsing an OBO document.
+#[derive(Debug)]
+pub enum OboError {
+    /// The document could not be read.
+    Io(std::io::Error),
+    /// Invalid line or stanza, with its line number (starting at 1).
+    Syntax { line: u64, message: String },
+}
+
+impl fmt::Display for OboError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OboError::Io(e) => write!(f, "{}", e),
+            OboError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for OboError {}
+
+/// Parser for OBO 1.4 flat files. It yields the triples of `[Term]` stanzas
+/// relevant to fuzon, using the same predicates as the OWL translation of OBO:
+/// `name` as rdfs:label, `synonym` as oboInOwl:has{Scope}Synonym and
+/// `is_a` as rdfs:subClassOf, `def` as IAO:0000115 and `comment` as rdfs:comment.
+/// Terms are typed as owl:Class. Obsolete terms are owl:deprecated, and
+/// `replaced_by` is the IAO term replaced by annotation.
+pub struct OboParser<R: BufRead> {
+    lines: Lines<R>,
+    line: u64,
+    idspaces: HashMap<String, String>,
+    ontology: Option<String>,
+    // Whether the current stanza is a [Term], or None in the header
+    in_term: Option<bool>,
+    // Line of the current stanza, and its tag-value pairs with their line
+    stanza_line: u64,
+    stanza: Vec<(u64, String, String)>,
+    pending: VecDeque<Result<Quad, OboError>>,
+    done: bool,
+}
+
+impl<R: BufRead> OboParser<R> {
+    pub fn new(reader: R) -> Self {
+        OboParser {
+            lines: reader.lines(),
+            line: 0,
+            idspaces: HashMap::new(),
+            ontology: None,
+            in_term: None,
+            stanza_line: 0,
+            stanza: Vec::new(),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Idspaces declared in the header so far, as prefixes and their namespace.
+    pub fn prefixes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.idspaces
+            .iter()
+            .map(|(prefix, base)| (prefix.as_str(), base.as_str()))
+    }
+
+    fn syntax_error(line: u64, message: impl Into<String>) -> OboError {
+        OboError::Syntax {
+            line,
+            message: message.into(),
+        }
+    }
+
+    // Handle a header tag-value pair.
+    fn header_tag(&mut self, tag: &str, value: &str) {
+        match tag {
+            "ontology" => self.ontology = Some(value.to_string()),
+            "idspace" => {
+                let mut parts = value.split_whitespace();
+                if let (Some(prefix), Some(base)) = (parts.next(), parts.next()) {
+                    self.idspaces.insert(prefix.to_string(), base.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Convert the current stanza to quads, once all its tags are known.
+    fn flush_stanza(&mut self) {
+        let stanza = std::mem::take(&mut self.stanza);
+        if self.in_term != Some(true) {
+            return;
+        }
+        let Some((_, _, id)) = stanza.iter().find(|(_, tag, _)| tag == "id") else {
+            let error = Self::syntax_error(self.stanza_line, "[Term] stanza without id");
+            self.pending.push_back(Err(error));
+            return;
+        };
+        let iri = expand_id(id, &self.idspaces, self.ontology.as_deref());
+        let subject = match NamedNode::new(&iri) {
+            Ok(subject) => subject,
+            Err(e) => {
+                let error = Self::syntax_error(self.stanza_line, format!("{}: {}", iri, e));
+                self.pending.push_back(Err(error));
+                return;
+            }
+        };
+
+        self.pending.push_back(Ok(Quad::new(
+            subject.clone(),
+            NamedNode::new_unchecked(RDF_TYPE),
+            NamedNode::new_unchecked(OWL_CLASS),
+            GraphName::DefaultGraph,
+        )));
+        for (line, tag, value) in stanza {
+            let statement = match tag.as_str() {
+                "name" => Ok((
+                    RDFS_LABEL.to_string(),
+                    Literal::new_simple_literal(unescape(&value)).into(),
+                )),
+                "synonym" => parse_synonym(&value)
+                    .map(|(text, scope)| {
+                        (
+                            scope.predicate().to_string(),
+                            Literal::new_simple_literal(text).into(),
+                        )
+                    })
+                    .map_err(|e| Self::syntax_error(line, e)),
+                // Cross references of definitions are ignored
+                "def" => parse_quoted(&value)
+                    .map(|(text, _)| {
+                        (
+                            IAO_DEFINITION.to_string(),
+                            Literal::new_simple_literal(text).into(),
+                        )
+                    })
+                    .map_err(|e| Self::syntax_error(line, format!("def {}", e))),
+                "comment" => Ok((
+                    RDFS_COMMENT.to_string(),
+                    Literal::new_simple_literal(unescape(&value)).into(),
+                )),
+                "is_obsolete" if value == "true" => {
+                    Ok((OWL_DEPRECATED.to_string(), Literal::from(true).into()))
+                }
+                // Trailing modifiers, e.g. {source="x"}, are ignored
+                "is_a" | "replaced_by" => {
+                    let predicate = match tag.as_str() {
+                        "is_a" => RDFS_SUBCLASS_OF,
+                        _ => IAO_REPLACED_BY,
+                    };
+                    let target = value.split_whitespace().next().unwrap_or_default();
+                    let iri = expand_id(target, &self.idspaces, self.ontology.as_deref());
+                    NamedNode::new(&iri)
+                        .map(|target| (predicate.to_string(), target.into()))
+                        .map_err(|e| Self::syntax_error(line, format!("{}: {}", iri, e)))
+                }
+                _ => continue,
+            };
+            let quad = statement.map(|(predicate, object): (String, RdfTerm)| {
+                Quad::new(
+                    subject.clone(),
+                    NamedNode::new_unchecked(predicate),
+                    object,
+                    GraphName::DefaultGraph,
+                )
+            });
+            self.pending.push_back(quad);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for OboParser<R> {
+    type Item = Result<Quad, OboError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            if self.done {
+                return None;
+            }
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(OboError::Io(e)));
+                }
+                None => {
+                    self.done = true;
+                    self.flush_stanza();
+                    continue;
+                }
+            };
+            self.line += 1;
+            let line = strip_comment(&line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                self.flush_stanza();
+                self.in_term = Some(line == "[Term]");
+                self.stanza_line = self.line;
+                continue;
+            }
+            let Some((tag, value)) = line.split_once(':') else {
+                return Some(Err(Self::syntax_error(self.line, "expected tag: value")));
+            };
+            let (tag, value) = (tag.trim(), value.trim());
+            match self.in_term {
+                None => self.header_tag(tag, value),
+                Some(true) => self
+                    .stanza
+                    .push((self.line, tag.to_string(), value.to_string())),
+                Some(false) => {}
+            }
+        }
+    }
+}
+
+// Remove the trailing comment of a line, starting at an unescaped '!'
+// outside of quoted strings.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            '!' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// Resolve escape sequences of OBO values.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('W') => out.push(' '),
+                Some(other) => out.push(other),
+                None => {}
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// Parse the value of a synonym tag: a quoted text followed by an optional
+// scope (RELATED by default), synonym type and cross references.
+fn parse_synonym(value: &str) -> Result<(String, SynonymScope), String> {
+    let (text, rest) = parse_quoted(value).map_err(|e| format!("synonym {}", e))?;
+    let scope = rest
+        .split_whitespace()
+        .next()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SynonymScope::Related);
+
+    Ok((text, scope))
+}
+
+// Split a value starting with a quoted text into the unescaped text and
+// the rest of the value.
+fn parse_quoted(value: &str) -> Result<(String, &str), String> {
+    let rest = value
+        .strip_prefix('"')
+        .ok_or_else(|| format!("should start with a quoted text: {}", value))?;
+    let mut escaped = false;
+    let end = rest
+        .char_indices()
+        .find(|(_, c)| match c {
+            _ if escaped => {
+                escaped = false;
+                false
+            }
+            '\\' => {
+                escaped = true;
+                false
+            }
+            '"' => true,
+            _ => false,
+        })
+        .map(|(i, _)| i)
+        .ok_or_else(|| format!("has an unterminated quoted text: {}", value))?;
+
+    Ok((unescape(&rest[..end]), &rest[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBO: &str = r#"format-version: 1.4
+ontology: cl
+idspace: EX http://example.org/ex#
+
+[Term]
+id: CL:0000738
+name: leukocyte ! comment
+def: "A \"white\" blood cell." [GOC:add]
+is_a: CL:0000988 ! hematopoietic cell
+synonym: "white blood cell" EXACT []
+synonym: "leucocyte" NARROW [] {source="x"}
+synonym: "WBC \"cell\"" []
+
+[Typedef]
+id: part_of
+name: part of
+
+[Term]
+id: EX:1
+name: example
+synonym: missing quotes EXACT []
+
+[Term]
+id: local
+name: local term
+"#;
+
+    #[test]
+    fn parse_obo() {
+        let mut parser = OboParser::new(OBO.as_bytes());
+        let quads = parser.by_ref().collect::<Vec<_>>();
+        assert_eq!(
+            parser.prefixes().collect::<Vec<_>>(),
+            [("EX", "http://example.org/ex#")]
+        );
+        // Each term is typed as a class
+        let (types, ok): (Vec<_>, Vec<_>) = quads
+            .iter()
+            .filter_map(|q| q.as_ref().ok())
+            .partition(|q| q.predicate.as_str() == RDF_TYPE);
+        assert_eq!(types.len(), 3);
+        assert_eq!(ok.len(), 8);
+        assert_eq!(
+            ok[0].subject.to_string(),
+            "<http://purl.obolibrary.org/obo/CL_0000738>"
+        );
+        assert_eq!(ok[0].predicate.as_str(), RDFS_LABEL);
+        assert_eq!(ok[1].predicate.as_str(), IAO_DEFINITION);
+        assert_eq!(ok[1].object.to_string(), r#""A \"white\" blood cell.""#);
+        assert_eq!(ok[2].predicate.as_str(), RDFS_SUBCLASS_OF);
+        assert_eq!(
+            ok[2].object.to_string(),
+            "<http://purl.obolibrary.org/obo/CL_0000988>"
+        );
+        assert_eq!(ok[3].predicate.as_str(), SynonymScope::Exact.predicate());
+        assert_eq!(ok[4].predicate.as_str(), SynonymScope::Narrow.predicate());
+        assert_eq!(ok[5].object.to_string(), r#""WBC \"cell\"""#);
+        assert_eq!(ok[5].predicate.as_str(), SynonymScope::Related.predicate());
+        assert_eq!(ok[6].subject.to_string(), "<http://example.org/ex#1>");
+        assert_eq!(
+            ok[7].subject.to_string(),
+            "<http://purl.obolibrary.org/obo/cl#local>"
+        );
+
+        let errors = quads
+            .iter()
+            .filter_map(|q| q.as_ref().err())
+            .collect::<Vec<_>>();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], OboError::Syntax { line: 21, .. }));
+    }
+
+    #[test]
+    fn synonym_scopes() {
+        for scope in SynonymScope::ALL {
+            assert_eq!(SynonymScope::from_predicate(scope.predicate()), Some(scope));
+            assert_eq!(scope.to_string().parse::<SynonymScope>(), Ok(scope));
+        }
+        assert_eq!("exact".parse::<SynonymScope>(), Ok(SynonymScope::Exact));
+        assert!("other".parse::<SynonymScope>().is_err());
+        assert_eq!(
+            expand_curie("HP:0000118"),
+            "http://purl.obolibrary.org/obo/HP_0000118"
+        );
+        assert_eq!(
+            compact_iri("http://purl.obolibrary.org/obo/HP_0000118").as_deref(),
+            Some("HP:0000118")
+        );
+        assert_eq!(compact_iri("http://purl.obolibrary.org/obo/cl#local"), None);
+    }
+}
@@ -0,0 +1,240 @@
+use std::{collections::BTreeMap, io::Read};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{obo, term_uri};
+
+/// Split a CURIE such as "CL:0000738" into its prefix and local part.
+/// IRIs (with "://" or "urn:") and identifiers with a "/" in their local
+/// part are not CURIEs.
+pub(crate) fn split_curie(id: &str) -> Option<(&str, &str)> {
+    if id.contains("://") || id.starts_with("urn:") {
+        return None;
+    }
+    id.split_once(':').filter(|(prefix, local)| {
+        !prefix.is_empty()
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+            && !local.is_empty()
+            && !local.contains('/')
+    })
+}
+
+/// Prefixes and their namespaces, used to compact IRIs into CURIEs
+/// (e.g. "CL:0000738") and to expand CURIEs back into IRIs.
+/// Gathered from the `@prefix` (or xmlns) declarations of sources and
+/// OBO idspaces, or loaded from a JSON-LD context or prefix map.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, Hash, PartialEq)]
+pub struct PrefixMap {
+    prefixes: BTreeMap<String, String>,
+}
+
+impl PrefixMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind a prefix to a namespace, replacing any previous binding of the
+    /// prefix or namespace. Empty prefixes and namespaces are ignored.
+    pub fn insert(&mut self, prefix: &str, namespace: &str) {
+        if !prefix.is_empty() && !namespace.is_empty() {
+            self.prefixes.retain(|_, ns| ns != namespace);
+            self.prefixes
+                .insert(prefix.to_string(), namespace.to_string());
+        }
+    }
+
+    /// Add all bindings of another map, which take precedence.
+    pub fn merge(&mut self, other: PrefixMap) {
+        for (prefix, ns) in other.iter() {
+            self.insert(prefix, ns);
+        }
+    }
+
+    /// Namespace bound to a prefix.
+    pub fn namespace(&self, prefix: &str) -> Option<&str> {
+        self.prefixes.get(prefix).map(|ns| ns.as_str())
+    }
+
+    /// Prefixes and their namespaces, sorted by prefix.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.prefixes
+            .iter()
+            .map(|(prefix, ns)| (prefix.as_str(), ns.as_str()))
+    }
+
+    /// Number of prefixes.
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    /// Compact an IRI into a CURIE using the longest matching namespace,
+    /// falling back to the OBO PURL convention. The IRI is returned as is
+    /// if no prefix applies.
+    pub fn compact(&self, iri: &str) -> String {
+        self.prefixes
+            .iter()
+            .filter_map(|(prefix, ns)| {
+                let local = iri.strip_prefix(ns.as_str())?;
+                let curie = format!("{}:{}", prefix, local);
+                split_curie(&curie).is_some().then_some((ns.len(), curie))
+            })
+            // Longest namespace first, then first prefix in order
+            .min_by(|a, b| b.0.cmp(&a.0))
+            .map(|(_, curie)| curie)
+            .or_else(|| obo::compact_iri(iri))
+            .unwrap_or_else(|| iri.to_string())
+    }
+
+    /// Expand a CURIE with a bound prefix into an IRI.
+    pub fn expand(&self, curie: &str) -> Option<String> {
+        let (prefix, local) = split_curie(curie)?;
+        self.namespace(prefix).map(|ns| format!("{}{}", ns, local))
+    }
+
+    /// URI of a term, as in `Term::uri`, from an IRI or CURIE. CURIEs with
+    /// a bound prefix are expanded with it, others as in `term_uri`.
+    pub fn term_uri(&self, id: &str) -> String {
+        self.expand(id.trim()).unwrap_or_else(|| term_uri(id))
+    }
+
+    /// Parse a JSON-LD context (`{"@context": {...}}`), a prefix map
+    /// (`{"CL": "http://purl.obolibrary.org/obo/CL_"}`) or a bioregistry
+    /// extended prefix map (a list of `{"prefix": ..., "uri_prefix": ...}`).
+    /// Context terms are only kept if they look like namespaces, i.e. their
+    /// IRI ends with "/", "#" or "_".
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let mut map = PrefixMap::new();
+        match value {
+            Value::Object(object) => match object.get("@context") {
+                Some(context) => map.add_context(context)?,
+                None => {
+                    for (prefix, ns) in object {
+                        let ns = ns
+                            .as_str()
+                            .ok_or_else(|| format!("namespace of {} is not a string", prefix))?;
+                        map.insert(prefix, ns);
+                    }
+                }
+            },
+            Value::Array(records) => {
+                for record in records {
+                    let field = |name: &str| record.get(name).and_then(|v| v.as_str());
+                    match (field("prefix"), field("uri_prefix")) {
+                        (Some(prefix), Some(ns)) => map.insert(prefix, ns),
+                        _ => return Err("records require a prefix and uri_prefix".to_string()),
+                    }
+                }
+            }
+            _ => return Err("expected a JSON object or list".to_string()),
+        }
+
+        Ok(map)
+    }
+
+    /// Read a prefix map from JSON, see `from_json`.
+    pub fn from_reader(reader: impl Read) -> Result<Self, String> {
+        let value: Value = serde_json::from_reader(reader).map_err(|e| e.to_string())?;
+        Self::from_json(&value)
+    }
+
+    // Add the prefixes of a JSON-LD context, which may be a list of contexts.
+    // Remote contexts are not supported.
+    fn add_context(&mut self, context: &Value) -> Result<(), String> {
+        match context {
+            Value::Array(contexts) => contexts.iter().try_for_each(|c| self.add_context(c)),
+            Value::Object(terms) => {
+                for (term, definition) in terms.iter().filter(|(t, _)| !t.starts_with('@')) {
+                    let ns = match definition {
+                        Value::String(ns) => Some(ns.as_str()),
+                        Value::Object(d) => d.get("@id").and_then(|id| id.as_str()),
+                        _ => None,
+                    };
+                    if let Some(ns) = ns.filter(|ns| ns.ends_with(['/', '#', '_'])) {
+                        self.insert(term, ns);
+                    }
+                }
+                Ok(())
+            }
+            Value::Null => Ok(()),
+            _ => Err("remote JSON-LD contexts are not supported".to_string()),
+        }
+    }
+}
+
+impl<'a> FromIterator<(&'a str, &'a str)> for PrefixMap {
+    fn from_iter<I: IntoIterator<Item = (&'a str, &'a str)>>(iter: I) -> Self {
+        let mut map = PrefixMap::new();
+        for (prefix, ns) in iter {
+            map.insert(prefix, ns);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn compact_expand() {
+        let map = PrefixMap::from_iter([
+            ("obo", "http://purl.obolibrary.org/obo/"),
+            ("CL", "http://purl.obolibrary.org/obo/CL_"),
+            ("schema", "http://schema.org/"),
+        ]);
+        let cl = "http://purl.obolibrary.org/obo/CL_0000738";
+        assert_eq!(map.compact(cl), "CL:0000738");
+        assert_eq!(map.compact("http://schema.org/name"), "schema:name");
+        assert_eq!(
+            map.compact("http://schema.org/a/b"),
+            "http://schema.org/a/b"
+        );
+        // OBO PURLs are compacted without an explicit prefix
+        assert_eq!(
+            PrefixMap::new().compact("http://purl.obolibrary.org/obo/UBERON_0000061"),
+            "UBERON:0000061"
+        );
+        assert_eq!(map.expand("CL:0000738").as_deref(), Some(cl));
+        assert_eq!(map.expand("foo:bar"), None);
+        assert_eq!(map.term_uri("schema:Person"), "http://schema.org/Person");
+        assert_eq!(
+            map.term_uri("GO:0008150"),
+            "http://purl.obolibrary.org/obo/GO_0008150"
+        );
+    }
+
+    #[test]
+    fn parse_maps() {
+        let context = json!({"@context": [
+            {"@vocab": "http://schema.org/", "schema": "http://schema.org/"},
+            {"name": "http://schema.org/name", "CL": {"@id": "http://purl.obolibrary.org/obo/CL_"}}
+        ]});
+        let map = PrefixMap::from_json(&context).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map.namespace("CL"),
+            Some("http://purl.obolibrary.org/obo/CL_")
+        );
+
+        let flat = json!({"ex": "http://example.org/"});
+        assert_eq!(
+            PrefixMap::from_json(&flat).unwrap().namespace("ex"),
+            Some("http://example.org/")
+        );
+
+        let records = json!([{"prefix": "go", "uri_prefix": "http://purl.obolibrary.org/obo/GO_"}]);
+        assert_eq!(PrefixMap::from_json(&records).unwrap().len(), 1);
+        assert!(PrefixMap::from_json(&json!({"ex": 1})).is_err());
+        assert!(
+            PrefixMap::from_json(&json!({"@context": "http://example.org/ctx.jsonld"})).is_err()
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use core::fmt;
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
+
+use crate::{index::words, normalize_score};
+
+/// Scores how well a label matches a query. Implementations must be
+/// thread-safe, as terms may be scored in parallel.
+pub trait Scorer: Send + Sync {
+    /// Raw score of a label for the query, higher is better, or None if
+    /// the label does not match.
+    fn score(&self, query: &str, label: &str) -> Option<f64>;
+
+    /// Maps a raw score into the [0, 1] range, higher is better.
+    fn normalize(&self, score: f64, _query: &str) -> f64 {
+        if score.is_nan() {
+            0.0
+        } else {
+            score.clamp(0.0, 1.0)
+        }
+    }
+
+    /// Raw score which `normalize` maps to the given normalized score, to
+    /// rank labels found otherwise (e.g. typos) among scored labels.
+    fn denormalize(&self, normalized: f64, _query: &str) -> f64 {
+        normalized
+    }
+
+    /// Whether only labels containing all characters of the query can
+    /// match, which lets matchers skip other labels using their index.
+    fn requires_all_chars(&self) -> bool {
+        false
+    }
+}
+
+/// Fuzzy subsequence matching (fzy), suited to autocompletion. Labels
+/// must contain all characters of the query, in order. Raw scores are
+/// unbounded, see `normalize_score`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fzy;
+
+impl Scorer for Fzy {
+    fn score(&self, query: &str, label: &str) -> Option<f64> {
+        rff::match_and_score(query, label).map(|m| m.1)
+    }
+
+    fn normalize(&self, score: f64, query: &str) -> f64 {
+        normalize_score(score, query)
+    }
+
+    fn denormalize(&self, normalized: f64, query: &str) -> f64 {
+        match normalized >= 1.0 {
+            true => f64::INFINITY,
+            false => normalized * query.chars().count() as f64,
+        }
+    }
+
+    fn requires_all_chars(&self) -> bool {
+        true
+    }
+}
+
+// Similarity between 0 and 1, where non-matches (0) are dropped.
+fn similarity(score: f64) -> Option<f64> {
+    (score > 0.0).then_some(score)
+}
+
+/// Jaro-Winkler similarity, which favours labels sharing a prefix with
+/// the query and tolerates transposed characters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JaroWinkler;
+
+impl Scorer for JaroWinkler {
+    fn score(&self, query: &str, label: &str) -> Option<f64> {
+        similarity(strsim::jaro_winkler(
+            &query.to_lowercase(),
+            &label.to_lowercase(),
+        ))
+    }
+}
+
+/// Levenshtein edit distance, normalized by the length of the longest
+/// string, which tolerates typos (e.g. "leukocite").
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Levenshtein;
+
+impl Scorer for Levenshtein {
+    fn score(&self, query: &str, label: &str) -> Option<f64> {
+        similarity(strsim::normalized_levenshtein(
+            &query.to_lowercase(),
+            &label.to_lowercase(),
+        ))
+    }
+}
+
+/// Like `Levenshtein`, where swapping adjacent characters is a single edit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DamerauLevenshtein;
+
+impl Scorer for DamerauLevenshtein {
+    fn score(&self, query: &str, label: &str) -> Option<f64> {
+        similarity(strsim::normalized_damerau_levenshtein(
+            &query.to_lowercase(),
+            &label.to_lowercase(),
+        ))
+    }
+}
+
+/// Token set ratio, which ignores the order and repetition of words
+/// (e.g. "cell B" matches "B cell"). Words shared by the query and label
+/// are compared with the remaining words of each, and the best
+/// normalized Levenshtein similarity is kept.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenSet;
+
+impl Scorer for TokenSet {
+    fn score(&self, query: &str, label: &str) -> Option<f64> {
+        let query: BTreeSet<String> = words(query).into_iter().collect();
+        let label: BTreeSet<String> = words(label).into_iter().collect();
+        let join = |words: Vec<&String>| {
+            words
+                .into_iter()
+                .map(|w| w.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let common = join(query.intersection(&label).collect());
+        let with_rest = |rest: Vec<&String>| match rest.is_empty() {
+            true => common.clone(),
+            false => format!("{} {}", common, join(rest)).trim().to_string(),
+        };
+        let query_rest = with_rest(query.difference(&label).collect());
+        let label_rest = with_rest(label.difference(&query).collect());
+
+        let mut score = strsim::normalized_levenshtein(&query_rest, &label_rest);
+        if !common.is_empty() {
+            score = score
+                .max(strsim::normalized_levenshtein(&common, &query_rest))
+                .max(strsim::normalized_levenshtein(&common, &label_rest));
+        }
+        similarity(score)
+    }
+}
+
+/// Maximum number of edits tolerated as typos in a query, by its length:
+/// none up to 2 characters, 1 up to 5, and 2 beyond.
+pub fn max_typos(query: &str) -> usize {
+    match query.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+// Scores labels with an inner scorer, or as typos of the query if they are
+// within a few edits of it. Typos are normalized to their edit similarity.
+pub(crate) struct TypoScorer<'a> {
+    pub scorer: &'a dyn Scorer,
+    // Edit similarity of the labels within a few edits of the query
+    pub typos: HashMap<&'a str, f64>,
+}
+
+impl Scorer for TypoScorer<'_> {
+    fn score(&self, query: &str, label: &str) -> Option<f64> {
+        let typo = self
+            .typos
+            .get(label)
+            .map(|similarity| self.scorer.denormalize(*similarity, query));
+        match (self.scorer.score(query, label), typo) {
+            (Some(score), Some(typo)) => Some(score.max(typo)),
+            (score, typo) => score.or(typo),
+        }
+    }
+
+    fn normalize(&self, score: f64, query: &str) -> f64 {
+        self.scorer.normalize(score, query)
+    }
+
+    fn denormalize(&self, normalized: f64, query: &str) -> f64 {
+        self.scorer.denormalize(normalized, query)
+    }
+}
+
+/// Built-in scorers, selectable by name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ScorerKind {
+    /// See `Fzy`.
+    #[default]
+    Fzy,
+    /// See `JaroWinkler`.
+    JaroWinkler,
+    /// See `Levenshtein`.
+    Levenshtein,
+    /// See `DamerauLevenshtein`.
+    DamerauLevenshtein,
+    /// See `TokenSet`.
+    TokenSet,
+}
+
+impl ScorerKind {
+    /// All built-in scorers.
+    pub const ALL: [ScorerKind; 5] = [
+        ScorerKind::Fzy,
+        ScorerKind::JaroWinkler,
+        ScorerKind::Levenshtein,
+        ScorerKind::DamerauLevenshtein,
+        ScorerKind::TokenSet,
+    ];
+
+    fn scorer(&self) -> &'static dyn Scorer {
+        match self {
+            ScorerKind::Fzy => &Fzy,
+            ScorerKind::JaroWinkler => &JaroWinkler,
+            ScorerKind::Levenshtein => &Levenshtein,
+            ScorerKind::DamerauLevenshtein => &DamerauLevenshtein,
+            ScorerKind::TokenSet => &TokenSet,
+        }
+    }
+}
+
+impl Scorer for ScorerKind {
+    fn score(&self, query: &str, label: &str) -> Option<f64> {
+        self.scorer().score(query, label)
+    }
+
+    fn normalize(&self, score: f64, query: &str) -> f64 {
+        self.scorer().normalize(score, query)
+    }
+
+    fn denormalize(&self, normalized: f64, query: &str) -> f64 {
+        self.scorer().denormalize(normalized, query)
+    }
+
+    fn requires_all_chars(&self) -> bool {
+        self.scorer().requires_all_chars()
+    }
+}
+
+impl FromStr for ScorerKind {
+    type Err = String;
+
+    /// Parse a scorer name case-insensitively, e.g. "fzy" or "jaro-winkler".
+    /// Underscores can be used instead of dashes, and "damerau" is short
+    /// for "damerau-levenshtein".
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_lowercase().replace('_', "-").as_str() {
+            "fzy" => Ok(ScorerKind::Fzy),
+            "jaro-winkler" => Ok(ScorerKind::JaroWinkler),
+            "levenshtein" => Ok(ScorerKind::Levenshtein),
+            "damerau-levenshtein" | "damerau" => Ok(ScorerKind::DamerauLevenshtein),
+            "token-set" => Ok(ScorerKind::TokenSet),
+            _ => Err(format!("unknown scorer: {}", name)),
+        }
+    }
+}
+
+impl fmt::Display for ScorerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ScorerKind::Fzy => "fzy",
+            ScorerKind::JaroWinkler => "jaro-winkler",
+            ScorerKind::Levenshtein => "levenshtein",
+            ScorerKind::DamerauLevenshtein => "damerau-levenshtein",
+            ScorerKind::TokenSet => "token-set",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scorers() {
+        // Typos do not match with fzy, but with edit distances
+        assert_eq!(Fzy.score("leukocite", "leukocyte"), None);
+        assert!(Fzy.requires_all_chars());
+        let typo = Levenshtein.score("leukocite", "leukocyte").unwrap();
+        assert!(typo > Levenshtein.score("leukocite", "lymphocyte").unwrap());
+        assert!(
+            DamerauLevenshtein.score("leukcoyte", "leukocyte").unwrap()
+                > Levenshtein.score("leukcoyte", "leukocyte").unwrap()
+        );
+        assert!(JaroWinkler.score("Leukocyte", "leukocyte") == Some(1.0));
+        assert_eq!(JaroWinkler.score("abc", "xyz"), None);
+        // Word order is ignored by token set ratio
+        assert_eq!(TokenSet.score("cell B", "B cell"), Some(1.0));
+        assert_eq!(TokenSet.score("B cell", "naive B cell"), Some(1.0));
+        assert!(TokenSet.score("T cell", "B cell").unwrap() < 1.0);
+        assert_eq!(TokenSet.score("", "B cell"), None);
+
+        for kind in ScorerKind::ALL {
+            assert_eq!(kind.to_string().parse::<ScorerKind>(), Ok(kind));
+        }
+        assert_eq!("Token_Set".parse(), Ok(ScorerKind::TokenSet));
+        assert_eq!("damerau".parse(), Ok(ScorerKind::DamerauLevenshtein));
+        assert!("soundex".parse::<ScorerKind>().is_err());
+        assert_eq!(max_typos("cd4"), 1);
+        assert_eq!(max_typos("lymphocite"), 2);
+    }
+
+    #[test]
+    fn typo_scores() {
+        for kind in ScorerKind::ALL {
+            let raw = kind.denormalize(0.8, "leukocite");
+            assert!((kind.normalize(raw, "leukocite") - 0.8).abs() < 1e-9);
+            assert_eq!(kind.normalize(kind.denormalize(1.0, "cell"), "cell"), 1.0);
+        }
+        let typos = TypoScorer {
+            scorer: &Fzy,
+            typos: HashMap::from([("leukocyte", 0.9)]),
+        };
+        let score = typos.score("leukocite", "leukocyte").unwrap();
+        assert!((typos.normalize(score, "leukocite") - 0.9).abs() < 1e-9);
+        assert_eq!(typos.score("leukocite", "lymphocyte"), None);
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use bzip2::bufread::MultiBzDecoder;
+use flate2::bufread::MultiGzDecoder;
+use oxrdfio::RdfFormat;
+use reqwest::{blocking::Client, header::CONTENT_TYPE, Url};
+use xz2::bufread::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+use crate::{sparql::is_sparql_source, table::TableFormat};
+
+/// Get an rdf reader along with its format from a path or URL.
+/// For URLs, the format is taken from the Content-Type header, then from the
+/// `format` query parameter or the extension. For files, it is taken from the
+/// extension. If none of these are conclusive, the content is sniffed.
+/// Compressed sources (gzip, xz, bzip2, zstd) are decompressed transparently.
+pub fn get_source(path: &str) -> Result<(Box<dyn BufRead>, RdfFormat)> {
+    get_source_with_format(path, None)
+}
+
+/// Get an rdf reader from a path or URL, with an optional format overriding detection.
+pub fn get_source_with_format(
+    path: &str,
+    format: Option<RdfFormat>,
+) -> Result<(Box<dyn BufRead>, RdfFormat)> {
+    let (mut reader, detected) = open_source(path)?;
+    let format = match format.or(detected) {
+        Some(format) => format,
+        None => sniff_format(reader.fill_buf()?),
+    };
+
+    Ok((reader, format))
+}
+
+/// Get a reader from a path or URL without interpreting its content.
+/// Compressed sources are decompressed transparently.
+pub fn get_reader(path: &str) -> Result<Box<dyn BufRead>> {
+    Ok(open_source(path)?.0)
+}
+
+// Open a path or URL and decompress it if needed. Also returns the RDF format
+// given by the Content-Type of URLs, or by the extension.
+fn open_source(path: &str) -> Result<(Box<dyn BufRead>, Option<RdfFormat>)> {
+    if is_sparql_source(path) {
+        // Terms are queried from the endpoint rather than parsed from a document
+        return Err(anyhow!(
+            "SPARQL sources can only be loaded with a TermMatcherBuilder: {}",
+            path
+        ));
+    }
+    if let Ok(url) = Url::parse(path) {
+        // Handle URL
+        let client = Client::new();
+        let response = client.get(url.clone()).send()?.error_for_status()?;
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(format_from_media_type);
+        let format = content_type.or_else(|| format_from_url(&url));
+        Ok((decompress(response, url.path())?, format)) // Return boxed reader for URL
+    } else {
+        // Handle file path
+        let file = File::open(path)?;
+        Ok((decompress(file, path)?, format_from_path(path))) // Return boxed reader for file
+    }
+}
+
+/// Format of a source: an RDF serialization, or a table of terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceFormat {
+    Rdf(RdfFormat),
+    /// OBO 1.4 flat file.
+    Obo,
+    Table(TableFormat),
+}
+
+impl From<RdfFormat> for SourceFormat {
+    fn from(format: RdfFormat) -> Self {
+        SourceFormat::Rdf(format)
+    }
+}
+
+impl From<TableFormat> for SourceFormat {
+    fn from(format: TableFormat) -> Self {
+        SourceFormat::Table(format)
+    }
+}
+
+impl SourceFormat {
+    /// Format of a source from its extension, if it is OBO or tabular.
+    /// RDF sources are detected when opened, see `get_source`.
+    pub fn from_path(path: &str) -> Option<Self> {
+        source_extension(path).and_then(|ext| SourceFormat::from_name(&ext))
+    }
+
+    // OBO or tabular format from an extension or media type.
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "obo" | "application/obo" | "text/obo" => Some(SourceFormat::Obo),
+            name => TableFormat::from_name(name).map(SourceFormat::Table),
+        }
+    }
+}
+
+/// Parse a user-provided format, which can be OBO, tabular (e.g. "csv")
+/// or RDF, see `parse_format`.
+pub fn parse_source_format(name: &str) -> Option<SourceFormat> {
+    SourceFormat::from_name(name).or_else(|| parse_format(name).map(SourceFormat::Rdf))
+}
+
+/// Compression formats supported for sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Bzip2,
+    Zstd,
+}
+
+impl Compression {
+    /// Compression from the extension of a path, e.g. "onto.ttl.gz".
+    pub fn from_path(path: &str) -> Option<Self> {
+        match Path::new(path)
+            .extension()?
+            .to_str()?
+            .to_lowercase()
+            .as_str()
+        {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "xz" => Some(Compression::Xz),
+            "bz2" => Some(Compression::Bzip2),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Compression from the magic bytes at the start of the content.
+    pub fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if head.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Wrap a compressed reader into a decompressing one.
+    pub fn decode(&self, reader: impl BufRead + 'static) -> Result<Box<dyn BufRead>> {
+        // Multi-stream decoders handle files concatenated from several archives
+        Ok(match self {
+            Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+            Compression::Xz => Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader))),
+            Compression::Bzip2 => Box::new(BufReader::new(MultiBzDecoder::new(reader))),
+            Compression::Zstd => Box::new(BufReader::new(ZstdDecoder::with_buffer(reader)?)),
+        })
+    }
+}
+
+// Decompress the raw content if needed.
+// The name is used to detect compression from its extension.
+fn decompress(raw: impl Read + 'static, name: &str) -> Result<Box<dyn BufRead>> {
+    let mut raw = BufReader::new(raw);
+    let compression = match Compression::from_path(name) {
+        Some(compression) => Some(compression),
+        None => Compression::from_magic(raw.fill_buf()?),
+    };
+
+    Ok(match compression {
+        Some(compression) => compression.decode(raw)?,
+        None => Box::new(raw),
+    })
+}
+
+/// Parse a user-provided format, given either as a file extension
+/// (e.g. "ttl", "owl") or as a media type (e.g. "text/turtle").
+pub fn parse_format(name: &str) -> Option<RdfFormat> {
+    format_from_extension(name).or_else(|| format_from_media_type(name))
+}
+
+// RDF format from a file extension, including common aliases for RDF/XML.
+fn format_from_extension(ext: &str) -> Option<RdfFormat> {
+    match ext.to_lowercase().as_str() {
+        "owl" | "rdf" | "xml" => Some(RdfFormat::RdfXml),
+        ext => RdfFormat::from_extension(ext),
+    }
+}
+
+// RDF format from a Content-Type header. Generic types such as text/plain
+// are frequently used for any file, and are not trusted.
+fn format_from_media_type(media_type: &str) -> Option<RdfFormat> {
+    let essence = media_type.split(';').next().unwrap_or_default().trim();
+    if essence.eq_ignore_ascii_case("text/plain") {
+        return None;
+    }
+    RdfFormat::from_media_type(essence)
+}
+
+// RDF format from the extension of a file path.
+fn format_from_path(path: &str) -> Option<RdfFormat> {
+    file_extension(path).and_then(|ext| format_from_extension(&ext))
+}
+
+// Extension of a file path, ignoring compression extensions
+// (e.g. "onto.ttl.gz" has extension "ttl").
+fn file_extension(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let path = match Compression::from_path(path.to_str()?) {
+        Some(_) => Path::new(path.file_stem()?),
+        None => path,
+    };
+    Some(path.extension()?.to_str()?.to_lowercase())
+}
+
+// Extension of a file path or URL, ignoring compression extensions.
+fn source_extension(path: &str) -> Option<String> {
+    match Url::parse(path) {
+        Ok(url) => file_extension(url.path_segments()?.next_back()?),
+        Err(_) => file_extension(path),
+    }
+}
+
+// RDF format from the `format` query parameter or the extension of a URL.
+fn format_from_url(url: &Url) -> Option<RdfFormat> {
+    url.query_pairs()
+        .find(|(key, _)| key == "format")
+        .and_then(|(_, value)| parse_format(&value))
+        .or_else(|| {
+            url.path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .and_then(format_from_path)
+        })
+}
+
+/// Guess the RDF format from the first bytes of the content.
+/// XML documents are parsed as RDF/XML, anything else as TriG, which
+/// also covers Turtle and N-Triples.
+pub fn sniff_format(head: &[u8]) -> RdfFormat {
+    let text = String::from_utf8_lossy(head);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    let first_tag = text.split('>').next().unwrap_or_default();
+    let is_xml = text.starts_with("<?")
+        || text.starts_with("<!")
+        // IRIs cannot contain whitespace, unlike XML tags with attributes
+        || (text.starts_with('<') && first_tag.contains(char::is_whitespace))
+        || text.starts_with("<rdf:RDF");
+
+    if is_xml {
+        RdfFormat::RdfXml
+    } else {
+        RdfFormat::TriG
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_format() {
+        let url = |u: &str| format_from_url(&Url::parse(u).unwrap());
+        assert_eq!(url("https://example.org/onto.owl"), Some(RdfFormat::RdfXml));
+        assert_eq!(
+            url("https://example.org/onto?format=ttl"),
+            Some(RdfFormat::Turtle)
+        );
+        assert_eq!(url("https://purl.obolibrary.org/obo/cl"), None);
+        assert_eq!(
+            format_from_path("../data/onto.nt"),
+            Some(RdfFormat::NTriples)
+        );
+        assert_eq!(format_from_path("../data/onto"), None);
+        assert_eq!(
+            format_from_path("../data/onto.rdf.xz"),
+            Some(RdfFormat::RdfXml)
+        );
+        assert_eq!(
+            url("https://example.org/onto.nt.gz"),
+            Some(RdfFormat::NTriples)
+        );
+        assert_eq!(
+            format_from_media_type("application/rdf+xml; charset=utf-8"),
+            Some(RdfFormat::RdfXml)
+        );
+        assert_eq!(format_from_media_type("text/plain"), None);
+        assert_eq!(parse_format("text/turtle"), Some(RdfFormat::Turtle));
+        assert_eq!(parse_format("OWL"), Some(RdfFormat::RdfXml));
+        assert_eq!(parse_format("foo"), None);
+    }
+
+    #[test]
+    fn sniff() {
+        let xml = "\u{feff}<?xml version=\"1.0\"?>\n<rdf:RDF>";
+        assert_eq!(sniff_format(xml.as_bytes()), RdfFormat::RdfXml);
+        let xml = "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">";
+        assert_eq!(sniff_format(xml.as_bytes()), RdfFormat::RdfXml);
+        let nt = "<http://example.org/a> <http://example.org/b> \"c\" .";
+        assert_eq!(sniff_format(nt.as_bytes()), RdfFormat::TriG);
+        let ttl = "@prefix ex: <http://example.org/> .";
+        assert_eq!(sniff_format(ttl.as_bytes()), RdfFormat::TriG);
+    }
+
+    #[test]
+    fn source_without_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schema");
+        std::fs::copy("../../data/test_schema.ttl", &path).unwrap();
+        let (_, format) = get_source(path.to_str().unwrap()).unwrap();
+        assert_eq!(format, RdfFormat::TriG);
+        let (_, format) =
+            get_source_with_format(path.to_str().unwrap(), Some(RdfFormat::Turtle)).unwrap();
+        assert_eq!(format, RdfFormat::Turtle);
+    }
+
+    #[test]
+    fn compressed_sources() {
+        use std::io::Write;
+
+        let content = std::fs::read("../../data/test_schema.ttl").unwrap();
+        let compress = |compression: Compression| -> Vec<u8> {
+            match compression {
+                Compression::Gzip => {
+                    let mut enc =
+                        flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                    enc.write_all(&content).unwrap();
+                    enc.finish().unwrap()
+                }
+                Compression::Xz => {
+                    let mut enc = xz2::write::XzEncoder::new(Vec::new(), 6);
+                    enc.write_all(&content).unwrap();
+                    enc.finish().unwrap()
+                }
+                Compression::Bzip2 => {
+                    let mut enc =
+                        bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                    enc.write_all(&content).unwrap();
+                    enc.finish().unwrap()
+                }
+                Compression::Zstd => zstd::encode_all(content.as_slice(), 0).unwrap(),
+            }
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        for (compression, ext) in [
+            (Compression::Gzip, "gz"),
+            (Compression::Xz, "xz"),
+            (Compression::Bzip2, "bz2"),
+            (Compression::Zstd, "zst"),
+        ] {
+            let data = compress(compression);
+            assert_eq!(Compression::from_magic(&data), Some(compression));
+
+            // Inner extension gives the format
+            let path = dir.path().join(format!("schema.ttl.{}", ext));
+            std::fs::write(&path, &data).unwrap();
+            let (mut reader, format) = get_source(path.to_str().unwrap()).unwrap();
+            assert_eq!(format, RdfFormat::Turtle);
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).unwrap();
+            assert_eq!(out, content);
+
+            // Magic bytes and sniffing without extensions
+            let path = dir.path().join(format!("schema_{}", ext));
+            std::fs::write(&path, &data).unwrap();
+            let (mut reader, format) = get_source(path.to_str().unwrap()).unwrap();
+            assert_eq!(format, RdfFormat::TriG);
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).unwrap();
+            assert_eq!(out, content);
+        }
+    }
+}
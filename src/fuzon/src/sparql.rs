@@ -0,0 +1,477 @@
+use core::fmt;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use oxrdf::{GraphName, Literal, NamedNode, Quad, Term as RdfTerm};
+use reqwest::{blocking::Client, header::ACCEPT, Url};
+use serde::Deserialize;
+
+/// Prefix marking a source as a SPARQL endpoint, e.g. `sparql+https://host/sparql`.
+pub const SPARQL_PREFIX: &str = "sparql+";
+const DEFAULT_PAGE_SIZE: usize = 10000;
+const DCT_MODIFIED: &str = "http://purl.org/dc/terms/modified";
+const RESULTS_JSON: &str = "application/sparql-results+json";
+
+/// Whether a source refers to a SPARQL endpoint.
+pub fn is_sparql_source(source: &str) -> bool {
+    source.starts_with(SPARQL_PREFIX)
+}
+
+/// A SPARQL endpoint used as a source of terms. It is given as a URL
+/// prefixed with `sparql+`, with optional parameters:
+/// + `graph`: only query this named graph.
+/// + `page_size`: number of results fetched per request.
+/// + `stamp`: SELECT query whose results identify the state of the data,
+///   used for caching. By default, this is the `dcterms:modified` date of
+///   the graph. Sources without either cannot be cached.
+///
+/// Other parameters are passed to the endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparqlSource {
+    pub endpoint: Url,
+    pub graph: Option<NamedNode>,
+    pub page_size: usize,
+    pub stamp_query: Option<String>,
+}
+
+/// Failure while querying a SPARQL endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SparqlError {
+    /// The endpoint could not be queried, or returned invalid results.
+    Request(String),
+    /// A result could not be converted to a triple.
+    InvalidTerm(String),
+}
+
+impl fmt::Display for SparqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SparqlError::Request(msg) => write!(f, "SPARQL request failed: {}", msg),
+            SparqlError::InvalidTerm(msg) => write!(f, "Invalid SPARQL result: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SparqlError {}
+
+// JSON serialization of SPARQL results
+#[derive(Debug, Deserialize)]
+struct SparqlResults {
+    head: SparqlHead,
+    results: SparqlBindings,
+}
+
+#[derive(Debug, Deserialize)]
+struct SparqlHead {
+    vars: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SparqlBindings {
+    bindings: Vec<HashMap<String, SparqlTerm>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SparqlTerm {
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+    #[serde(rename = "xml:lang")]
+    lang: Option<String>,
+    datatype: Option<String>,
+}
+
+impl SparqlTerm {
+    fn to_rdf(&self) -> Result<RdfTerm, SparqlError> {
+        let invalid =
+            |e: &dyn fmt::Display| SparqlError::InvalidTerm(format!("{}: {}", self.value, e));
+        match (self.kind.as_str(), &self.lang, &self.datatype) {
+            ("uri", _, _) => Ok(NamedNode::new(&self.value).map_err(|e| invalid(&e))?.into()),
+            ("literal" | "typed-literal", Some(lang), _) => {
+                Ok(Literal::new_language_tagged_literal(&self.value, lang)
+                    .map_err(|e| invalid(&e))?
+                    .into())
+            }
+            ("literal" | "typed-literal", None, Some(datatype)) => {
+                let datatype = NamedNode::new(datatype).map_err(|e| invalid(&e))?;
+                Ok(Literal::new_typed_literal(&self.value, datatype).into())
+            }
+            ("literal" | "typed-literal", None, None) => {
+                Ok(Literal::new_simple_literal(&self.value).into())
+            }
+            (kind, _, _) => Err(invalid(&format!("unsupported term type {}", kind))),
+        }
+    }
+}
+
+impl SparqlSource {
+    /// Parse a `sparql+` source into an endpoint and its parameters.
+    pub fn parse(source: &str) -> Result<Self> {
+        let url = source
+            .strip_prefix(SPARQL_PREFIX)
+            .ok_or_else(|| anyhow!("Not a SPARQL source: {}", source))?;
+        let mut endpoint = Url::parse(url)?;
+        let mut graph = None;
+        let mut page_size = DEFAULT_PAGE_SIZE;
+        let mut stamp_query = None;
+        let mut params = Vec::new();
+        for (key, value) in endpoint.query_pairs() {
+            match key.as_ref() {
+                "graph" => graph = Some(NamedNode::new(value.as_ref())?),
+                "page_size" => page_size = value.parse()?,
+                "stamp" => stamp_query = Some(value.to_string()),
+                _ => params.push((key.to_string(), value.to_string())),
+            }
+        }
+        if page_size == 0 {
+            return Err(anyhow!("page_size must be positive: {}", source));
+        }
+        endpoint.set_query(None);
+        if !params.is_empty() {
+            endpoint.query_pairs_mut().extend_pairs(params);
+        }
+
+        Ok(SparqlSource {
+            endpoint,
+            graph,
+            page_size,
+            stamp_query,
+        })
+    }
+
+    /// SELECT query returning one page of triples for the predicates.
+    /// Objects may be literals (labels) or IRIs (parent classes).
+    pub fn select_query<'a>(
+        &self,
+        predicates: impl Iterator<Item = &'a str>,
+        offset: usize,
+    ) -> String {
+        let values = predicates
+            .filter_map(|p| NamedNode::new(p).ok())
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let pattern = match &self.graph {
+            Some(graph) => format!("GRAPH {} {{ ?s ?p ?o . }}", graph),
+            None => "?s ?p ?o .".to_string(),
+        };
+
+        format!(
+            "SELECT ?s ?p ?o WHERE {{\n  VALUES ?p {{ {} }}\n  {}\n  FILTER(isIRI(?s) && !isBlank(?o))\n}}\nORDER BY ?s ?p ?o\nLIMIT {} OFFSET {}",
+            values, pattern, self.page_size, offset
+        )
+    }
+
+    /// SELECT query used to identify the state of the data, if any: the
+    /// `stamp` parameter, or else the `dcterms:modified` date of the graph.
+    pub fn stamp_query(&self) -> Option<String> {
+        match (&self.stamp_query, &self.graph) {
+            (Some(query), _) => Some(query.clone()),
+            (None, Some(graph)) => Some(format!(
+                "SELECT ?modified WHERE {{ {{ {g} <{m}> ?modified }} UNION {{ GRAPH {g} {{ {g} <{m}> ?modified }} }} }}",
+                g = graph,
+                m = DCT_MODIFIED
+            )),
+            (None, None) => None,
+        }
+    }
+
+    /// Stamp for caching, made of the source and the values of the first
+    /// result of the stamp query. Fails without stamp query, or if it has no
+    /// results, as changes to the data could not be detected: such sources
+    /// are not cached.
+    pub fn stamp(&self, source: &str) -> Result<String> {
+        let query = self.stamp_query().ok_or_else(|| {
+            anyhow!(
+                "Cannot stamp SPARQL source {} for caching, set a graph with a {} date or a stamp query",
+                source,
+                DCT_MODIFIED
+            )
+        })?;
+        let values = self.stamp_values(&query)?.ok_or_else(|| {
+            anyhow!(
+                "Cannot stamp SPARQL source {} for caching, no results for: {}",
+                source,
+                query
+            )
+        })?;
+
+        Ok(format!("{}-{}", source, values))
+    }
+
+    // Values of the first result of a query, joined with dashes, if any.
+    fn stamp_values(&self, query: &str) -> Result<Option<String>, SparqlError> {
+        let results = self.select(query)?;
+        let values = results.results.bindings.first().map(|row| {
+            results
+                .head
+                .vars
+                .iter()
+                .filter_map(|var| row.get(var).map(|t| t.value.as_str()))
+                .collect::<Vec<_>>()
+                .join("-")
+        });
+
+        Ok(values.filter(|v| !v.is_empty()))
+    }
+
+    /// Iterate over triples for the predicates, fetching results page by page.
+    pub fn quads<'a>(&'a self, predicates: impl Iterator<Item = &'a str>) -> SparqlQuads<'a> {
+        SparqlQuads {
+            source: self,
+            predicates: predicates.collect(),
+            offset: 0,
+            page: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    fn select(&self, query: &str) -> Result<SparqlResults, SparqlError> {
+        let request = |e: &dyn fmt::Display| SparqlError::Request(e.to_string());
+        let response = Client::new()
+            .get(self.endpoint.clone())
+            .query(&[("query", query)])
+            .header(ACCEPT, RESULTS_JSON)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| request(&e))?;
+
+        serde_json::from_reader(response).map_err(|e| request(&e))
+    }
+}
+
+/// Label triples of a SPARQL endpoint, fetched lazily one page at a time.
+pub struct SparqlQuads<'a> {
+    source: &'a SparqlSource,
+    predicates: Vec<&'a str>,
+    offset: usize,
+    page: std::vec::IntoIter<HashMap<String, SparqlTerm>>,
+    done: bool,
+}
+
+impl Iterator for SparqlQuads<'_> {
+    type Item = Result<Quad, SparqlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.page.next() {
+                return Some(row_to_quad(&row));
+            }
+            if self.done {
+                return None;
+            }
+            let query = self
+                .source
+                .select_query(self.predicates.iter().copied(), self.offset);
+            match self.source.select(&query) {
+                Ok(results) => {
+                    let rows = results.results.bindings;
+                    // A partial page is the last one
+                    self.done = rows.len() < self.source.page_size;
+                    self.offset += rows.len();
+                    self.page = rows.into_iter();
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+fn row_to_quad(row: &HashMap<String, SparqlTerm>) -> Result<Quad, SparqlError> {
+    let get = |var: &str| {
+        row.get(var)
+            .ok_or_else(|| SparqlError::InvalidTerm(format!("missing variable ?{}", var)))?
+            .to_rdf()
+    };
+    let subject = match get("s")? {
+        RdfTerm::NamedNode(node) => node,
+        term => return Err(SparqlError::InvalidTerm(format!("{}: not an IRI", term))),
+    };
+    let predicate = match get("p")? {
+        RdfTerm::NamedNode(node) => node,
+        term => return Err(SparqlError::InvalidTerm(format!("{}: not an IRI", term))),
+    };
+
+    Ok(Quad::new(
+        subject,
+        predicate,
+        get("o")?,
+        GraphName::DefaultGraph,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    const LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+
+    // Serve canned SPARQL results, paged according to the LIMIT/OFFSET of queries.
+    fn mock_endpoint(rows: Vec<(&'static str, &'static str)>, requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // Skip headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let target = request_line.split(' ').nth(1).unwrap();
+                let url = Url::parse(&format!("http://localhost{}", target)).unwrap();
+                let query = url
+                    .query_pairs()
+                    .find(|(k, _)| k == "query")
+                    .map(|(_, v)| v.to_string())
+                    .unwrap();
+
+                let body = if query.contains(DCT_MODIFIED) {
+                    // Graphs have no modification date
+                    r#"{"head":{"vars":["modified"]},"results":{"bindings":[]}}"#.to_string()
+                } else if query.contains("COUNT") {
+                    r#"{"head":{"vars":["triples"]},"results":{"bindings":[{"triples":{"type":"literal","value":"42"}}]}}"#.to_string()
+                } else {
+                    let number = |key: &str| -> usize {
+                        let start = query.find(key).unwrap() + key.len();
+                        query[start..]
+                            .split_whitespace()
+                            .next()
+                            .unwrap()
+                            .parse()
+                            .unwrap()
+                    };
+                    let (limit, offset) = (number("LIMIT "), number("OFFSET "));
+                    let bindings = rows
+                        .iter()
+                        .skip(offset)
+                        .take(limit)
+                        .map(|(uri, label)| {
+                            format!(
+                                r#"{{"s":{{"type":"uri","value":"{}"}},"p":{{"type":"uri","value":"{}"}},"o":{{"type":"literal","value":"{}","xml:lang":"en"}}}}"#,
+                                uri, LABEL, label
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    format!(
+                        r#"{{"head":{{"vars":["s","p","o"]}},"results":{{"bindings":[{}]}}}}"#,
+                        bindings
+                    )
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    RESULTS_JSON,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        format!("sparql+http://127.0.0.1:{}/sparql", port)
+    }
+
+    #[test]
+    fn parse_source() {
+        let source = SparqlSource::parse(
+            "sparql+https://example.org/sparql?graph=http://example.org/g&page_size=5&key=abc",
+        )
+        .unwrap();
+        assert_eq!(
+            source.endpoint.as_str(),
+            "https://example.org/sparql?key=abc"
+        );
+        assert_eq!(
+            source.graph,
+            Some(NamedNode::new_unchecked("http://example.org/g"))
+        );
+        assert_eq!(source.page_size, 5);
+        assert!(source.stamp_query().unwrap().contains(DCT_MODIFIED));
+        assert!(source
+            .select_query([LABEL].into_iter(), 10)
+            .contains("GRAPH <http://example.org/g>"));
+        assert!(SparqlSource::parse("https://example.org/sparql").is_err());
+        assert!(SparqlSource::parse("sparql+https://example.org/sparql?page_size=0").is_err());
+    }
+
+    #[test]
+    fn paged_quads() {
+        let rows = vec![
+            ("http://example.org/a", "alpha"),
+            ("http://example.org/b", "beta"),
+            ("http://example.org/c", "gamma"),
+        ];
+        // A full page, then a partial one
+        let source = format!("{}?page_size=2", mock_endpoint(rows.clone(), 2));
+        let sparql = SparqlSource::parse(&source).unwrap();
+        let quads = sparql
+            .quads([LABEL].into_iter())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(quads.len(), 3);
+        assert_eq!(quads[2].subject.to_string(), "<http://example.org/c>");
+
+        // Results go through the same pipeline as RDF files
+        let source = mock_endpoint(rows.clone(), 1);
+        let matcher = crate::TermMatcher::from_paths(vec![&source]).unwrap();
+        let top = matcher.top_terms("beta", 1);
+        assert_eq!(top[0].uri, "http://example.org/b");
+        assert_eq!(top[0].lang.as_deref(), Some("en"));
+
+        // Custom stamp queries identify the state of the data
+        let source = format!(
+            "{}?stamp=SELECT%20(COUNT(*)%20AS%20%3Fn)%20WHERE%20%7B%20%3Fs%20%3Fp%20%3Fo%20%7D",
+            mock_endpoint(rows, 1)
+        );
+        let stamp = SparqlSource::parse(&source)
+            .unwrap()
+            .stamp(&source)
+            .unwrap();
+        assert_eq!(stamp, format!("{}-42", source));
+    }
+
+    #[test]
+    fn stamp_without_modified() {
+        // Sources without graph or stamp query cannot be cached
+        let source = "sparql+https://example.org/sparql";
+        let sparql = SparqlSource::parse(source).unwrap();
+        assert!(sparql.stamp_query().is_none());
+        assert!(sparql.stamp(source).is_err());
+
+        // Neither can graphs without modification date
+        let source = format!(
+            "{}?graph=http://example.org/g",
+            mock_endpoint(Vec::new(), 1)
+        );
+        let err = SparqlSource::parse(&source)
+            .unwrap()
+            .stamp(&source)
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Cannot stamp SPARQL source"));
+
+        // Custom stamp queries without results cannot stamp the source
+        let source = format!(
+            "{}?stamp=SELECT%20%3Fd%20WHERE%20%7B%20%3Fg%20%3C{}%3E%20%3Fd%20%7D",
+            mock_endpoint(Vec::new(), 1),
+            DCT_MODIFIED
+        );
+        let err = SparqlSource::parse(&source)
+            .unwrap()
+            .stamp(&source)
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Cannot stamp SPARQL source"));
+    }
+}
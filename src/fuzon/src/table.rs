@@ -0,0 +1,271 @@
+use std::{collections::HashMap, io::BufRead};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{IngestError, SourceReport, Term, SKOS_ALT_LABEL, SKOS_PREF_LABEL};
+
+/// Formats of tabular sources, with one term per row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl TableFormat {
+    /// Tabular format from an extension (e.g. "csv") or media type (e.g. "text/csv").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" | "text/csv" => Some(TableFormat::Csv),
+            "tsv" | "tab" | "text/tab-separated-values" => Some(TableFormat::Tsv),
+            "jsonl" | "ndjson" | "application/jsonl" | "application/x-ndjson" => {
+                Some(TableFormat::JsonLines)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Columns (or JSON fields) of tabular sources holding the terms.
+/// The label is indexed as skos:prefLabel and synonyms as skos:altLabel.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableMapping {
+    /// Column of the term identifiers.
+    pub id: String,
+    /// Column of the primary labels.
+    pub label: String,
+    /// Column of the synonyms, ignored if missing.
+    pub synonyms: Option<String>,
+    /// Separator between synonyms in a cell.
+    pub separator: String,
+    /// Template turning identifiers into URIs, e.g. "https://icd.who.int/{id}".
+    /// Templates without `{id}` are used as a prefix.
+    pub uri_template: Option<String>,
+}
+
+impl Default for TableMapping {
+    fn default() -> Self {
+        TableMapping {
+            id: "id".to_string(),
+            label: "label".to_string(),
+            synonyms: Some("synonyms".to_string()),
+            separator: "|".to_string(),
+            uri_template: None,
+        }
+    }
+}
+
+impl TableMapping {
+    /// URI of a term from its identifier.
+    pub fn uri(&self, id: &str) -> String {
+        match &self.uri_template {
+            Some(template) if template.contains("{id}") => template.replace("{id}", id),
+            Some(prefix) => format!("{}{}", prefix, id),
+            None => id.to_string(),
+        }
+    }
+
+    // Terms of a row from the values of its id, label and synonyms columns.
+    fn terms(&self, id: &str, label: &str, synonyms: Vec<String>) -> Result<Vec<Term>, String> {
+        let (id, label) = (id.trim(), label.trim());
+        if id.is_empty() || label.is_empty() {
+            return Err(format!("missing {} or {}", self.id, self.label));
+        }
+        let uri = self.uri(id);
+        let term = |label: &str, predicate: &str| Term {
+            uri: uri.clone(),
+            label: label.to_string(),
+            lang: None,
+            predicate: predicate.to_string(),
+        };
+        let mut terms = vec![term(label, SKOS_PREF_LABEL)];
+        terms.extend(
+            synonyms
+                .iter()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| term(s, SKOS_ALT_LABEL)),
+        );
+
+        Ok(terms)
+    }
+
+    fn split_synonyms(&self, cell: &str) -> Vec<String> {
+        if self.separator.is_empty() {
+            return vec![cell.to_string()];
+        }
+        cell.split(self.separator.as_str())
+            .map(String::from)
+            .collect()
+    }
+}
+
+/// Gather terms from a tabular source. Invalid rows are skipped in lenient mode.
+pub(crate) fn gather_table(
+    source: &str,
+    reader: impl BufRead,
+    format: TableFormat,
+    mapping: &TableMapping,
+    lenient: bool,
+) -> Result<(Vec<Term>, SourceReport), IngestError> {
+    let mut terms = Vec::new();
+    let mut errors = 0;
+    let mut first_error = None;
+    let mut on_row = |row: Result<Vec<Term>, IngestError>| -> Result<(), IngestError> {
+        match row {
+            Ok(mut out) => terms.append(&mut out),
+            Err(e) if lenient => {
+                errors += 1;
+                first_error.get_or_insert(e);
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    };
+
+    match format {
+        TableFormat::Csv | TableFormat::Tsv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(if format == TableFormat::Tsv {
+                    b'\t'
+                } else {
+                    b','
+                })
+                .quoting(format == TableFormat::Csv)
+                .flexible(true)
+                .from_reader(reader);
+            let headers = reader
+                .headers()
+                .map_err(|e| IngestError::new(source, e))?
+                .clone();
+            let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+            let missing = |name: &str| IngestError::new(source, format!("missing column {}", name));
+            let id_col = column(&mapping.id).ok_or_else(|| missing(&mapping.id))?;
+            let label_col = column(&mapping.label).ok_or_else(|| missing(&mapping.label))?;
+            let synonyms_col = mapping.synonyms.as_deref().and_then(column);
+
+            for record in reader.records() {
+                let row = match record {
+                    // Failures to read the source cannot be skipped
+                    Err(e) if e.is_io_error() => return Err(IngestError::new(source, e)),
+                    Err(e) => {
+                        let line = e.position().map(|p| p.line());
+                        Err(IngestError::new(source, e).at_line(line))
+                    }
+                    Ok(record) => {
+                        let cell = |i: usize| record.get(i).unwrap_or_default();
+                        let synonyms = synonyms_col
+                            .map(|i| mapping.split_synonyms(cell(i)))
+                            .unwrap_or_default();
+                        let line = record.position().map(|p| p.line());
+                        mapping
+                            .terms(cell(id_col), cell(label_col), synonyms)
+                            .map_err(|e| IngestError::new(source, e).at_line(line))
+                    }
+                };
+                on_row(row)?;
+            }
+        }
+        TableFormat::JsonLines => {
+            for (i, line) in reader.lines().enumerate() {
+                let line = line.map_err(|e| IngestError::new(source, e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let row = serde_json::from_str::<HashMap<String, Value>>(&line)
+                    .map_err(|e| e.to_string())
+                    .and_then(|object| {
+                        let field = |name: &str| match object.get(name) {
+                            Some(Value::String(s)) => s.clone(),
+                            Some(Value::Null) | None => String::new(),
+                            Some(value) => value.to_string(),
+                        };
+                        let synonyms = match mapping.synonyms.as_ref().and_then(|s| object.get(s)) {
+                            Some(Value::Array(values)) => values
+                                .iter()
+                                .filter_map(|v| v.as_str().map(String::from))
+                                .collect(),
+                            Some(Value::String(s)) => mapping.split_synonyms(s),
+                            _ => Vec::new(),
+                        };
+                        mapping.terms(&field(&mapping.id), &field(&mapping.label), synonyms)
+                    })
+                    .map_err(|e| IngestError::new(source, e).at_line(Some(i as u64 + 1)));
+                on_row(row)?;
+            }
+        }
+    }
+    let report = SourceReport {
+        source: source.to_string(),
+        terms: terms.len(),
+        errors,
+        first_error,
+    };
+
+    Ok((terms, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabular_sources() {
+        let csv = "id,label,synonyms\nA00,Cholera,\"Asiatic cholera|Epidemic cholera\"\nA01,Typhoid fever,\n";
+        let tsv = "id\tlabel\tsynonyms\nA00\tCholera\tAsiatic cholera|Epidemic cholera\nA01\tTyphoid fever\t\n";
+        let jsonl = "{\"id\": \"A00\", \"label\": \"Cholera\", \"synonyms\": [\"Asiatic cholera\", \"Epidemic cholera\"]}\n\n{\"id\": \"A01\", \"label\": \"Typhoid fever\"}\n";
+        let mapping = TableMapping {
+            uri_template: Some("http://example.org/icd/{id}".to_string()),
+            ..Default::default()
+        };
+        for (content, format) in [
+            (csv, TableFormat::Csv),
+            (tsv, TableFormat::Tsv),
+            (jsonl, TableFormat::JsonLines),
+        ] {
+            let (terms, report) =
+                gather_table("test", content.as_bytes(), format, &mapping, false).unwrap();
+            assert_eq!(report.terms, 4);
+            assert_eq!(terms[0].uri, "http://example.org/icd/A00");
+            assert_eq!(terms[0].predicate, SKOS_PREF_LABEL);
+            assert_eq!(terms[2].label, "Epidemic cholera");
+            assert_eq!(terms[2].predicate, SKOS_ALT_LABEL);
+        }
+    }
+
+    #[test]
+    fn column_mapping() {
+        let content = "code,name\nX1,first\n,missing id\nX3,third\n";
+        let mapping = TableMapping {
+            id: "code".to_string(),
+            label: "name".to_string(),
+            uri_template: Some("urn:x:".to_string()),
+            ..Default::default()
+        };
+        let err = gather_table(
+            "test",
+            content.as_bytes(),
+            TableFormat::Csv,
+            &mapping,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(err.line, Some(3));
+        assert_eq!(err.to_string(), "test:3: missing code or name");
+
+        let (terms, report) =
+            gather_table("test", content.as_bytes(), TableFormat::Csv, &mapping, true).unwrap();
+        assert_eq!(terms.len(), 2);
+        assert_eq!(terms[1].uri, "urn:x:X3");
+        assert_eq!(report.errors, 1);
+
+        // Unknown columns cannot be skipped
+        let default = TableMapping::default();
+        assert!(
+            gather_table("test", content.as_bytes(), TableFormat::Csv, &default, true).is_err()
+        );
+    }
+}
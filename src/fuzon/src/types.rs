@@ -0,0 +1,240 @@
+use core::fmt;
+use std::{collections::HashMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::PrefixMap;
+
+pub(crate) const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+pub(crate) const OWL_CLASS: &str = "http://www.w3.org/2002/07/owl#Class";
+
+const CLASS_TYPES: [&str; 2] = [OWL_CLASS, "http://www.w3.org/2000/01/rdf-schema#Class"];
+const PROPERTY_TYPES: [&str; 11] = [
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#Property",
+    "http://www.w3.org/2002/07/owl#ObjectProperty",
+    "http://www.w3.org/2002/07/owl#DatatypeProperty",
+    "http://www.w3.org/2002/07/owl#AnnotationProperty",
+    "http://www.w3.org/2002/07/owl#FunctionalProperty",
+    "http://www.w3.org/2002/07/owl#InverseFunctionalProperty",
+    "http://www.w3.org/2002/07/owl#TransitiveProperty",
+    "http://www.w3.org/2002/07/owl#SymmetricProperty",
+    "http://www.w3.org/2002/07/owl#AsymmetricProperty",
+    "http://www.w3.org/2002/07/owl#ReflexiveProperty",
+    "http://www.w3.org/2002/07/owl#IrreflexiveProperty",
+];
+// Types of resources which are neither classes, properties nor individuals
+const OTHER_TYPES: [&str; 1] = ["http://www.w3.org/2002/07/owl#Ontology"];
+
+/// Broad kind of a resource, derived from its rdf:type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TermKind {
+    /// owl:Class or rdfs:Class.
+    Class,
+    /// rdf:Property or any OWL property type.
+    Property,
+    /// Any other typed resource, e.g. owl:NamedIndividual or instances of a class.
+    Individual,
+}
+
+impl TermKind {
+    /// Kind of a resource from its types (full IRIs).
+    /// Classes and properties take precedence, untyped resources have no kind.
+    pub fn from_types<'a>(types: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        let mut kind = None;
+        for iri in types {
+            if CLASS_TYPES.contains(&iri) {
+                return Some(TermKind::Class);
+            } else if PROPERTY_TYPES.contains(&iri) {
+                kind = Some(TermKind::Property);
+            } else if kind.is_none() && !OTHER_TYPES.contains(&iri) {
+                kind = Some(TermKind::Individual);
+            }
+        }
+        kind
+    }
+}
+
+impl FromStr for TermKind {
+    type Err = String;
+
+    /// Parse a kind case-insensitively, e.g. "class" or "Property".
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "class" => Ok(TermKind::Class),
+            "property" => Ok(TermKind::Property),
+            "individual" => Ok(TermKind::Individual),
+            _ => Err(format!("unknown term kind: {}", name)),
+        }
+    }
+}
+
+impl fmt::Display for TermKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TermKind::Class => "class",
+            TermKind::Property => "property",
+            TermKind::Individual => "individual",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Restricts terms by the rdf:type of their URI.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeFilter {
+    /// Resources of a broad kind (class, property or individual).
+    Kind(TermKind),
+    /// Resources with this exact type, given as an IRI or CURIE, see `expand`.
+    Type(String),
+}
+
+impl TypeFilter {
+    /// Filter with its type IRI or CURIE expanded with the prefixes, as in
+    /// `PrefixMap::term_uri`, to match types in the same form as `Term::uri`.
+    pub fn expand(&self, prefixes: &PrefixMap) -> Self {
+        match self {
+            TypeFilter::Kind(kind) => TypeFilter::Kind(*kind),
+            TypeFilter::Type(id) => TypeFilter::Type(prefixes.term_uri(id)),
+        }
+    }
+}
+
+impl FromStr for TypeFilter {
+    type Err = String;
+
+    /// Parse a kind name ("class", "property", "individual"), or else a
+    /// type IRI or CURIE, kept as is until expanded.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if name.trim().is_empty() {
+            return Err("empty type".to_string());
+        }
+        Ok(name
+            .parse()
+            .map(TypeFilter::Kind)
+            .unwrap_or_else(|_| TypeFilter::Type(name.trim().to_string())))
+    }
+}
+
+impl fmt::Display for TypeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeFilter::Kind(kind) => write!(f, "{}", kind),
+            TypeFilter::Type(uri) => write!(f, "{}", uri),
+        }
+    }
+}
+
+/// rdf:type of URIs, in the same form as `Term::uri`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct TypeIndex {
+    types: HashMap<String, Vec<String>>,
+}
+
+impl TypeIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that a URI has a type. Duplicates are ignored.
+    pub fn add_type(&mut self, uri: &str, rdf_type: &str) {
+        let types = self.types.entry(uri.to_string()).or_default();
+        if !types.iter().any(|t| t == rdf_type) {
+            types.push(rdf_type.to_string());
+        }
+    }
+
+    /// Add all types of another index.
+    pub fn merge(&mut self, other: TypeIndex) {
+        for (uri, types) in other.types {
+            for rdf_type in types {
+                self.add_type(&uri, &rdf_type);
+            }
+        }
+    }
+
+    /// Types of a URI, in insertion order.
+    pub fn types(&self, uri: &str) -> &[String] {
+        self.types.get(uri).map_or(&[], |t| t.as_slice())
+    }
+
+    /// Broad kind of a URI, if it is typed.
+    pub fn kind(&self, uri: &str) -> Option<TermKind> {
+        TermKind::from_types(self.types(uri).iter().map(|t| t.as_str()))
+    }
+
+    /// Whether a URI passes any of the filters, whose types must be expanded.
+    pub fn matches(&self, uri: &str, filters: &[TypeFilter]) -> bool {
+        filters.iter().any(|filter| match filter {
+            TypeFilter::Kind(kind) => self.kind(uri) == Some(*kind),
+            TypeFilter::Type(rdf_type) => self.types(uri).contains(rdf_type),
+        })
+    }
+
+    /// Number of typed URIs.
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn term_kinds() {
+        let mut index = TypeIndex::new();
+        index.add_type(
+            "http://schema.org/Person",
+            "http://www.w3.org/2000/01/rdf-schema#Class",
+        );
+        index.add_type(
+            "http://schema.org/name",
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#Property",
+        );
+        index.add_type("http://schema.org/Monday", "http://schema.org/DayOfWeek");
+        index.add_type(
+            "http://example.org/onto",
+            "http://www.w3.org/2002/07/owl#Ontology",
+        );
+
+        assert_eq!(
+            index.kind("http://schema.org/Person"),
+            Some(TermKind::Class)
+        );
+        assert_eq!(
+            index.kind("http://schema.org/name"),
+            Some(TermKind::Property)
+        );
+        assert_eq!(
+            index.kind("http://schema.org/Monday"),
+            Some(TermKind::Individual)
+        );
+        assert_eq!(index.kind("http://example.org/onto"), None);
+        assert_eq!(index.kind("http://example.org/untyped"), None);
+
+        let filters: Vec<TypeFilter> = ["property", "<http://schema.org/DayOfWeek>"]
+            .iter()
+            .map(|f| {
+                f.parse::<TypeFilter>()
+                    .unwrap()
+                    .expand(&PrefixMap::default())
+            })
+            .collect();
+        assert!(index.matches("http://schema.org/name", &filters));
+        assert!(index.matches("http://schema.org/Monday", &filters));
+        assert!(!index.matches("http://schema.org/Person", &filters));
+        assert_eq!("Class".parse::<TermKind>(), Ok(TermKind::Class));
+        assert!("".parse::<TypeFilter>().is_err());
+        assert_eq!(
+            "HP:0000118"
+                .parse::<TypeFilter>()
+                .unwrap()
+                .expand(&PrefixMap::default()),
+            TypeFilter::Type("http://purl.obolibrary.org/obo/HP_0000118".to_string())
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Term, TermMatcher};
+use crate::{ConceptMatch, SearchOptions, Term, TermMatcher};
 use std::io::stdout;
 
 use anyhow::Result;
@@ -11,13 +11,27 @@ use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
     style::{Color, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
 
+/// How search results are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResultStyle {
+    /// One result per concept instead of one per label.
+    pub concepts: bool,
+    /// Show URIs as CURIEs when a prefix of the matcher applies.
+    pub curie: bool,
+}
+
 // Main interaction loop listening to keys, running the search and rendering the UI on each key
 // stroke.
-pub fn interactive(matcher: &TermMatcher, top_n: Option<usize>) -> Result<()> {
+pub fn interactive(
+    matcher: &TermMatcher,
+    top_n: Option<usize>,
+    options: &SearchOptions,
+    style: ResultStyle,
+) -> Result<()> {
     // Raw mode does not react to SIGINT, hence we capture it below
     enable_raw_mode()?;
     let mut stdout = stdout();
@@ -27,10 +41,12 @@ pub fn interactive(matcher: &TermMatcher, top_n: Option<usize>) -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let mut query = String::new();
+    // Position of the highlighted result, whose definition and lineage are shown
+    let mut selected: usize = 0;
 
     loop {
         terminal.draw(|f| {
-            draw_ui(f, &query, &matcher, top_n);
+            draw_ui(f, &query, &mut selected, matcher, top_n, options, style);
         })?;
 
         if let Event::Key(key) = event::read()? {
@@ -39,10 +55,16 @@ pub fn interactive(matcher: &TermMatcher, top_n: Option<usize>) -> Result<()> {
                 KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) && c == 'c' => {
                     break
                 }
-                KeyCode::Char(c) => query.push(c),
+                KeyCode::Char(c) => {
+                    query.push(c);
+                    selected = 0;
+                }
                 KeyCode::Backspace => {
                     query.pop();
+                    selected = 0;
                 }
+                KeyCode::Up => selected = selected.saturating_sub(1),
+                KeyCode::Down => selected += 1,
                 KeyCode::Esc => break,
                 _ => {}
             }
@@ -56,34 +78,152 @@ pub fn interactive(matcher: &TermMatcher, top_n: Option<usize>) -> Result<()> {
     Ok(())
 }
 
-// Draws the TUI elements
-pub fn draw_ui(f: &mut Frame, query: &str, matcher: &TermMatcher, top_n: Option<usize>) {
+// Draws the TUI elements, clamping the selected position to the results
+pub fn draw_ui(
+    f: &mut Frame,
+    query: &str,
+    selected: &mut usize,
+    matcher: &TermMatcher,
+    top_n: Option<usize>,
+    options: &SearchOptions,
+    style: ResultStyle,
+) {
     // Split the frame into vertical sections
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Min(1),
+                Constraint::Length(5),
+                Constraint::Length(3),
+            ]
+            .as_ref(),
+        )
         .split(f.area());
 
     // Input block (displays what the user it typing)
     let input = Paragraph::new(query).block(Block::default().borders(Borders::ALL).title("Query"));
 
-    // Only show hits
-    let results = search(matcher, query, top_n)
-        .into_iter()
-        .filter(|(_, score)| *score > 0.0)
-        .collect::<Vec<(&Term, f64)>>();
+    // Only show hits, either one per label or one per concept
+    // Each hit is kept with its URI and label to show its details
+    let results: Vec<(String, String, String, f64)> = if style.concepts {
+        search_concepts(matcher, query, top_n, options)
+            .into_iter()
+            .filter(|m| m.score > 0.0)
+            .map(|m| {
+                let result = format_concept(matcher, &m, style.curie);
+                (
+                    result,
+                    m.concept.uri.clone(),
+                    m.concept.label.clone(),
+                    m.score,
+                )
+            })
+            .collect()
+    } else {
+        search(matcher, query, top_n, options)
+            .into_iter()
+            .filter(|(_, score)| *score > 0.0)
+            .map(|(term, score)| {
+                let result = format_term(matcher, term, style.curie);
+                (result, term.uri.clone(), term.label.clone(), score)
+            })
+            .collect()
+    };
+    *selected = (*selected).min(results.len().saturating_sub(1));
+    let selected = *selected;
 
     // Results block, shows search results
     let items: Vec<ListItem> = results
         .iter()
-        .map(|(term, score)| ListItem::new(format!("[{}] {}", score, term)))
+        .map(|(result, _, _, score)| ListItem::new(format!("[{}] {}", score, result)))
         .collect();
     let list = List::new(items)
         .block(Block::default().borders(Borders::ALL).title("Results"))
-        .style(Style::default().fg(Color::White));
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Yellow));
+    let mut state = ListState::default().with_selected(Some(selected));
+
+    // Definition block, tells apart hits with similar labels
+    let definition = results
+        .get(selected)
+        .and_then(|(_, uri, _, _)| matcher.definitions().get(uri))
+        .unwrap_or_default();
+    let definition = Paragraph::new(definition)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL).title("Definition"));
+
+    // Lineage block, shows the ancestors of the selected hit from the root
+    let breadcrumb = results
+        .get(selected)
+        .map(|(_, uri, label, _)| breadcrumb(matcher, uri, label))
+        .unwrap_or_default();
+    let lineage =
+        Paragraph::new(breadcrumb).block(Block::default().borders(Borders::ALL).title("Lineage"));
 
     f.render_widget(input, chunks[0]);
-    f.render_widget(list, chunks[1]);
+    f.render_stateful_widget(list, chunks[1], &mut state);
+    f.render_widget(definition, chunks[2]);
+    f.render_widget(lineage, chunks[3]);
+}
+
+// Labels from the root to the input URI, following first parents, e.g.
+// "cell > hematopoietic cell > leukocyte".
+pub fn breadcrumb(matcher: &TermMatcher, uri: &str, label: &str) -> String {
+    let mut labels: Vec<String> = matcher
+        .lineage(uri)
+        .into_iter()
+        .rev()
+        .map(|c| c.label)
+        .collect();
+    labels.push(label.to_string());
+    labels.join(" > ")
+}
+
+// URI as a CURIE if requested and a prefix applies, as is otherwise.
+pub fn format_uri(matcher: &TermMatcher, uri: &str, curie: bool) -> String {
+    if curie {
+        matcher.compact(uri)
+    } else {
+        uri.to_string()
+    }
+}
+
+// Suffix flagging deprecated URIs along with their replacements, empty otherwise.
+pub fn deprecation_note(matcher: &TermMatcher, uri: &str, curie: bool) -> String {
+    let deprecations = matcher.deprecations();
+    match deprecations.replaced_by(uri) {
+        _ if !deprecations.is_deprecated(uri) => String::new(),
+        [] => " [deprecated]".to_string(),
+        replacements => {
+            let replacements: Vec<String> = replacements
+                .iter()
+                .map(|r| format_uri(matcher, r, curie))
+                .collect();
+            format!(" [deprecated, replaced by {}]", replacements.join(", "))
+        }
+    }
+}
+
+// Term with its URI, flagged if deprecated.
+pub fn format_term(matcher: &TermMatcher, term: &Term, curie: bool) -> String {
+    let shown = Term {
+        uri: format_uri(matcher, &term.uri, curie),
+        ..term.clone()
+    };
+    format!("{}{}", shown, deprecation_note(matcher, &term.uri, curie))
+}
+
+// Concept match with its URI, flagged if deprecated.
+pub fn format_concept(matcher: &TermMatcher, m: &ConceptMatch, curie: bool) -> String {
+    let mut shown = m.clone();
+    shown.concept.uri = format_uri(matcher, &m.concept.uri, curie);
+    format!(
+        "{}{}",
+        shown,
+        deprecation_note(matcher, &m.concept.uri, curie)
+    )
 }
 
 // Helper to run the fuzzy search and filter top hits if requested.
@@ -91,12 +231,17 @@ pub fn search<'a>(
     matcher: &'a TermMatcher,
     query: &str,
     top_n: Option<usize>,
+    options: &SearchOptions,
 ) -> Vec<(&'a Term, f64)> {
-    let mut results = matcher.rank_terms(query);
-    if let Some(top_n) = top_n {
-        let take_n = top_n.min(results.len());
-        results = results[..take_n].to_vec();
-    }
+    matcher.rank_top_terms(query, top_n.unwrap_or(usize::MAX), options)
+}
 
-    results
+// Helper to run the fuzzy search grouped by concept and filter top hits if requested.
+pub fn search_concepts(
+    matcher: &TermMatcher,
+    query: &str,
+    top_n: Option<usize>,
+    options: &SearchOptions,
+) -> Vec<ConceptMatch> {
+    matcher.top_concepts_with(query, top_n.unwrap_or(usize::MAX), options)
 }
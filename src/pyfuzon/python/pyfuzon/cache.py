@@ -38,5 +38,4 @@ def cache_by_source(sources: list[str]):
 
 def load_by_source(sources: list[str]) -> TermMatcher:
     """Load and combine single-source cache entries into a combined TermMatcher."""
-    terms = _load_by_source(sources)
-    return TermMatcher(terms)
+    return TermMatcher.from_matcher(_load_by_source(sources))
@@ -1,48 +1,314 @@
-from dataclasses import dataclass
 from typing import Self
 
-from dataclasses import dataclass
+from pyfuzon import (
+    Concept,
+    Deprecations,
+    Hierarchy,
+    Matcher,
+    PrefixMap,
+    Term,
+    TypeIndex,
+)
 
-from pyfuzon import Term, score_terms, parse_files, load_terms, dump_terms
 
-
-@dataclass
 class TermMatcher:
-    """Fuzzy matches terms from RDF terminologies to input queries."""
+    """Fuzzy matches terms from RDF terminologies to input queries.
+    Terms are indexed once, when the matcher is built or loaded, by a fuzon
+    `Matcher` which answers all queries.
+    """
+
+    def __init__(
+        self,
+        terms: list[Term],
+        hierarchy: Hierarchy | None = None,
+        type_index: TypeIndex | None = None,
+        deprecations: Deprecations | None = None,
+        prefixes: PrefixMap | None = None,
+        normalize: str = "default",
+    ):
+        self._matcher = Matcher(
+            terms, hierarchy, type_index, deprecations, prefixes, normalize
+        )
+
+    @classmethod
+    def from_matcher(cls, matcher: Matcher) -> Self:
+        """Wrap an indexed fuzon Matcher, e.g. loaded from the cache."""
+        term_matcher = cls.__new__(cls)
+        term_matcher._matcher = matcher
+        return term_matcher
+
+    @property
+    def terms(self) -> list[Term]:
+        """Terms of the matcher, described by the definition of their URI."""
+        return self._matcher.terms()
+
+    @property
+    def hierarchy(self) -> Hierarchy:
+        return self._matcher.hierarchy()
+
+    @property
+    def type_index(self) -> TypeIndex:
+        return self._matcher.type_index()
+
+    @property
+    def deprecations(self) -> Deprecations:
+        return self._matcher.deprecations()
+
+    @property
+    def prefixes(self) -> PrefixMap:
+        return self._matcher.prefixes()
+
+    @property
+    def normalize(self) -> str:
+        """Normalization of labels and queries, as comma-separated steps."""
+        return self._matcher.normalize()
+
+    def __len__(self) -> int:
+        return len(self._matcher)
+
+    def top(
+        self,
+        query: str,
+        n: int=5,
+        lang: str | None = None,
+        scopes: list[str] | None = None,
+        roots: list[str] | None = None,
+        types: list[str] | None = None,
+        include_deprecated: bool = False,
+        curie: bool = False,
+        fields: dict[str, float] | None = None,
+        scorer: str | None = None,
+    ) -> list[Term]:
+        """Return the n terms most similar to input query.
+        If lang is provided, only labels in that language (or untagged) are considered.
+        If scopes are provided (e.g. ["exact"]), only synonyms with these scopes
+        are considered, along with other labels.
+        If roots are provided (IRIs or CURIEs, e.g. ["UBERON:0000061"]),
+        only terms under these roots in the hierarchy are considered.
+        If types are provided as kinds ("class", "property", "individual")
+        or type IRIs or CURIEs, only terms matching one of them are considered.
+        Deprecated terms are excluded unless include_deprecated is set,
+        see `replaced_by`.
+        With curie, URIs are returned as CURIEs (e.g. "CL:0000738") when a
+        prefix applies, see `compact`.
+        With fields, each URI is ranked once by its labels, synonyms and
+        description instead, which also finds words of descriptions. Fields
+        are weighted with a dict, e.g. {"definition": 2.0}, where missing
+        fields keep their default weight ({} for all defaults).
+        Labels are scored with fzy, or with scorer: "jaro-winkler",
+        "levenshtein", "damerau-levenshtein" (typos) or "token-set" (word order).
+        """
+        return self._matcher.rank(
+            query,
+            n,
+            lang=lang,
+            scopes=scopes,
+            roots=roots,
+            types=types,
+            include_deprecated=include_deprecated,
+            curie=curie,
+            fields=fields,
+            scorer=scorer,
+        )
 
-    terms: list[Term]
+    def rank(
+        self,
+        query: str,
+        lang: str | None = None,
+        scopes: list[str] | None = None,
+        roots: list[str] | None = None,
+        types: list[str] | None = None,
+        include_deprecated: bool = False,
+        curie: bool = False,
+        fields: dict[str, float] | None = None,
+        scorer: str | None = None,
+    ) -> list[Term]:
+        """Return the terms matching the query, ranked by similarity.
+        Terms whose labels do not match the query are left out, unlike
+        `score` which returns a score for every term.
+        If lang is provided, only labels in that language (or untagged) are returned.
+        If scopes are provided, only synonyms with these scopes are returned,
+        along with other labels.
+        If roots are provided, only terms under these roots are returned.
+        If types are provided, only terms matching one of them are returned.
+        Deprecated terms are only returned with include_deprecated.
+        With curie, URIs are returned as CURIEs when a prefix applies.
+        With fields, URIs are ranked by their weighted fields, and those
+        matching no field are not returned.
+        Labels are scored with fzy, or with the named scorer.
+        """
+        return self._matcher.rank(
+            query,
+            lang=lang,
+            scopes=scopes,
+            roots=roots,
+            types=types,
+            include_deprecated=include_deprecated,
+            curie=curie,
+            fields=fields,
+            scorer=scorer,
+        )
+
+    def top_concepts(
+        self,
+        query: str,
+        n: int=5,
+        lang: str | None = None,
+        scopes: list[str] | None = None,
+        roots: list[str] | None = None,
+        types: list[str] | None = None,
+        include_deprecated: bool = False,
+        curie: bool = False,
+        fields: dict[str, float] | None = None,
+        scorer: str | None = None,
+    ) -> list[Concept]:
+        """Return the n concepts most similar to input query.
+        Labels are grouped by URI, and each concept is ranked by its best matching label,
+        or by its weighted fields if given.
+        """
+        return self._matcher.rank_concepts(
+            query,
+            n,
+            lang=lang,
+            scopes=scopes,
+            roots=roots,
+            types=types,
+            include_deprecated=include_deprecated,
+            curie=curie,
+            fields=fields,
+            scorer=scorer,
+        )
 
-    def top(self, query: str, n: int=5) -> list[Term]:
-        """Return the n terms most similar to input query."""
-        return self.rank(query)[:n]
+    def rank_concepts(
+        self,
+        query: str,
+        lang: str | None = None,
+        scopes: list[str] | None = None,
+        roots: list[str] | None = None,
+        types: list[str] | None = None,
+        include_deprecated: bool = False,
+        curie: bool = False,
+        fields: dict[str, float] | None = None,
+        scorer: str | None = None,
+    ) -> list[Concept]:
+        """Return the concepts (URIs with their labels) matching the query,
+        ranked by similarity. Concepts with no matching label are left out.
+        """
+        return self._matcher.rank_concepts(
+            query,
+            lang=lang,
+            scopes=scopes,
+            roots=roots,
+            types=types,
+            include_deprecated=include_deprecated,
+            curie=curie,
+            fields=fields,
+            scorer=scorer,
+        )
 
-    def rank(self, query: str) -> list[Term]:
-        """Return all terms, ranked by query similarity."""
-        scores = self.score(query)
-        ranks = [
-            i[0] for i in
-            sorted(enumerate(scores), key=lambda x:x[1], reverse=True)
-        ]
-        return [self.terms[rank] for rank in ranks]
+    def score(self, query: str, scorer: str | None = None) -> list[float]:
+        """Return the similarity score to the query of each term, 0 for terms
+        which do not match.
+        Terms are scored with fzy, or with the named scorer, against their
+        labels normalized when building the matcher, see `from_files`.
+        """
+        return self._matcher.score(query, scorer)
+
+    def parents(self, uri: str) -> list[str]:
+        """Return the URIs of the direct parents of a term.
+        The hierarchy is only gathered with `from_files(..., hierarchy=True)`.
+        """
+        return self._matcher.parents(uri)
+
+    def ancestors(self, uri: str) -> list[str]:
+        """Return the URIs of all ancestors of a term, nearest first."""
+        return self._matcher.ancestors(uri)
+
+    def replaced_by(self, uri: str) -> list[str]:
+        """Return the URIs replacing a deprecated term, if any."""
+        return self._matcher.replaced_by(uri)
+
+    def lookup(self, uri: str, curie: bool = False) -> list[Term]:
+        """Return the terms (labels) of a URI or CURIE, empty if it is unknown.
+        With curie, URIs are returned as CURIEs when a prefix applies.
+        """
+        return self._matcher.lookup(uri, curie)
+
+    def compact(self, uri: str) -> str:
+        """Return a URI as a CURIE if a prefix applies, as is otherwise.
+        Prefixes are declared in sources or given to `from_files`, and OBO
+        PURLs are always compacted.
+        """
+        return self._matcher.compact(uri)
 
-    def score(self, query: str) -> list[float]:
-        """Return all terms with a similarity score to the query."""
-        return score_terms(query, self.terms)
+    def subtree(self, roots: list[str]) -> set[str]:
+        """Return the URIs of the roots (IRIs or CURIEs) and all their descendants."""
+        return self._matcher.subtree(roots)
 
     @classmethod
-    def from_files(cls, paths: list[str]) -> Self:
+    def from_files(
+        cls,
+        paths: list[str],
+        add_predicates: list[str] | None = None,
+        remove_predicates: list[str] | None = None,
+        replace_predicates: list[str] | None = None,
+        lenient: bool = False,
+        format: str | None = None,
+        table: dict[str, str] | None = None,
+        synonyms: bool = False,
+        hierarchy: bool = False,
+        types: bool = False,
+        prefixes: PrefixMap | None = None,
+        definition_predicates: list[str] | None = None,
+        normalize: str = "default",
+    ) -> Self:
         """Create a TermMatcher from a list of paths to source ontologies.
         Both filepaths and URLs are supported.
+        Annotation predicates (full IRIs) used to gather labels can be added to,
+        removed from, or replace the default set.
+        In lenient mode, invalid triples are skipped instead of raising an error.
+        The RDF format is detected from the extension, Content-Type or content,
+        unless given as an extension (e.g. "ttl", "csv") or media type.
+        Tabular sources (CSV, TSV, JSON lines) are read from the columns given
+        in `table` (id, label, synonyms, separator, uri_template).
+        With synonyms, oboInOwl synonyms of all scopes are also gathered.
+        With hierarchy, parent classes (rdfs:subClassOf, skos:broader) are
+        also gathered, see `parents` and `ancestors`.
+        With types, rdf:type is also gathered to filter terms by kind or type.
+        Prefixes declared in sources are kept to return CURIEs, and
+        can be complemented or overridden with prefixes, e.g.
+        `PrefixMap.from_file("context.jsonld")`.
+        Terms are described by the definition of their URI, taken from
+        IAO:0000115, skos:definition or rdfs:comment, or else from
+        definition_predicates (full IRIs, by decreasing priority).
+        Labels and queries are normalized before matching with the
+        comma-separated steps of normalize: diacritics (NFKD and accent
+        folding), case, punctuation, greek (spell out Greek letters),
+        "default" for the first three, or "none".
         """
-        terms = parse_files(paths)
-        return cls(terms)
+        return cls.from_matcher(
+            Matcher.from_files(
+                paths,
+                add_predicates=add_predicates,
+                remove_predicates=remove_predicates,
+                replace_predicates=replace_predicates,
+                lenient=lenient,
+                format=format,
+                table=table,
+                synonyms=synonyms,
+                hierarchy=hierarchy,
+                types=types,
+                prefixes=prefixes,
+                definition_predicates=definition_predicates,
+                normalize=normalize,
+            )
+        )
 
     @classmethod
     def load(cls, path):
         """Deserialize a TermMatcher object from disk."""
-        terms = load_terms(path)
-        return cls(terms)
+        return cls.from_matcher(Matcher.load(path))
 
     def dump(self, path):
-        """Serialize to disk."""
-        dump_terms(self.terms, path)
+        """Serialize to disk, along with the normalization of labels."""
+        self._matcher.dump(path)
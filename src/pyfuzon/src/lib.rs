@@ -1,12 +1,20 @@
 use core::fmt;
-use pyo3::prelude::*;
-use std::path::PathBuf;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
-use fuzon::{cache, gather_terms, get_source, TermMatcher};
-use rff;
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+
+use fuzon::{
+    cache, get_reader, parse_source_format, table::TableMapping, Normalizer, Scorer, ScorerKind,
+    SynonymScope, TermMatcher, TermMatcherBuilder,
+};
 
 /// A struct to represent a term from an ontology.
 /// This mirrors fuzon::Term while making it easier to use in Python.
+/// The description is the definition of the term's URI, if any.
 #[pyclass]
 #[derive(Debug, Clone)]
 pub struct Term {
@@ -14,80 +22,899 @@ pub struct Term {
     pub uri: String,
     #[pyo3(get, set)]
     pub label: String,
+    #[pyo3(get, set)]
+    pub lang: Option<String>,
+    #[pyo3(get, set)]
+    pub predicate: String,
+    #[pyo3(get, set)]
+    pub description: Option<String>,
 }
 
 #[pymethods]
 impl Term {
     #[new]
-    pub fn new(uri: String, label: String) -> Self {
-        Term { uri, label }
+    #[pyo3(signature = (uri, label, lang=None, predicate=RDFS_LABEL.to_string(), description=None))]
+    pub fn new(
+        uri: String,
+        label: String,
+        lang: Option<String>,
+        predicate: String,
+        description: Option<String>,
+    ) -> Self {
+        Term {
+            uri,
+            label,
+            lang,
+            predicate,
+            description,
+        }
+    }
+
+    /// Whether the label is in the input language (untagged labels match any language).
+    pub fn matches_lang(&self, lang: &str) -> bool {
+        fuzon::Term::from(self.clone()).matches_lang(lang)
+    }
+
+    /// Scope of the label if it is a synonym (EXACT, BROAD, NARROW or RELATED).
+    #[getter]
+    pub fn synonym_scope(&self) -> Option<String> {
+        fuzon::Term::from(self.clone())
+            .synonym_scope()
+            .map(|s| s.to_string())
     }
 
     pub fn __str__(&self) -> String {
-        format!("{} ({})", self.label, self.uri)
+        self.to_string()
     }
 
     pub fn __repr__(&self) -> String {
-        format!("{} ({})", self.label, self.uri)
+        self.to_string()
     }
 }
 
 impl fmt::Display for Term {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} ({})", self.label, self.uri)
+        write!(f, "{}", fuzon::Term::from(self.clone()))
     }
 }
 
-/// Returns a vector of similarity scores for each term to the query
-#[pyfunction]
-pub fn score_terms(query: String, terms: Vec<Term>) -> PyResult<Vec<f64>> {
-    let scores: Vec<f64> = terms
+impl From<fuzon::Term> for Term {
+    fn from(term: fuzon::Term) -> Self {
+        Term {
+            uri: term.uri,
+            label: term.label,
+            lang: term.lang,
+            predicate: term.predicate,
+            description: None,
+        }
+    }
+}
+
+impl From<Term> for fuzon::Term {
+    fn from(term: Term) -> Self {
+        fuzon::Term {
+            uri: term.uri,
+            label: term.label,
+            lang: term.lang,
+            predicate: term.predicate,
+        }
+    }
+}
+
+// Terms of a matcher, described by the definition of their URI.
+fn matcher_terms(mut matcher: TermMatcher) -> Vec<Term> {
+    let terms = std::mem::take(&mut matcher.terms);
+    let definitions = matcher.definitions();
+    terms
         .into_iter()
-        .map(|t| {
-            rff::match_and_score(&query, &t.label.to_string())
-                .and_then(|m| Some(m.1.to_owned()))
-                .unwrap_or(0.0)
+        .map(|term| Term {
+            description: definitions.get(&term.uri).map(String::from),
+            ..Term::from(term)
         })
+        .collect()
+}
+
+// Definitions of URIs from the descriptions of their terms.
+fn term_definitions(terms: &[Term]) -> fuzon::Definitions {
+    let mut definitions = fuzon::Definitions::new();
+    for term in terms {
+        if let Some(description) = &term.description {
+            definitions.insert(&term.uri, description);
+        }
+    }
+    definitions
+}
+
+/// A URI ranked by its best matching label, along with all its labels.
+/// This mirrors fuzon::ConceptMatch.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct Concept {
+    #[pyo3(get)]
+    pub uri: String,
+    #[pyo3(get)]
+    pub label: String,
+    #[pyo3(get)]
+    pub alt_labels: Vec<String>,
+    #[pyo3(get)]
+    pub matched_label: String,
+    #[pyo3(get)]
+    pub description: Option<String>,
+    #[pyo3(get)]
+    pub score: f64,
+}
+
+#[pymethods]
+impl Concept {
+    pub fn __str__(&self) -> String {
+        format!("{} ({})", self.label, self.uri)
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("{} ({})", self.label, self.uri)
+    }
+}
+
+impl From<fuzon::ConceptMatch> for Concept {
+    fn from(m: fuzon::ConceptMatch) -> Self {
+        Concept {
+            uri: m.concept.uri,
+            label: m.concept.label,
+            alt_labels: m.concept.alt_labels,
+            matched_label: m.matched_label,
+            description: None,
+            score: m.score,
+        }
+    }
+}
+
+/// Parent edges between URIs (rdfs:subClassOf, skos:broader, OBO is_a).
+/// This wraps fuzon::Hierarchy, URIs are in the same form as Term.uri.
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct Hierarchy(fuzon::Hierarchy);
+
+#[pymethods]
+impl Hierarchy {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that parent is a direct parent of child.
+    pub fn add_edge(&mut self, child: &str, parent: &str) {
+        self.0.add_edge(child, parent);
+    }
+
+    /// Direct parents of a URI.
+    pub fn parents(&self, uri: &str) -> Vec<String> {
+        self.0.parents(uri).to_vec()
+    }
+
+    /// All ancestors of a URI, nearest first.
+    pub fn ancestors(&self, uri: &str) -> Vec<String> {
+        self.0
+            .ancestors(uri)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// All descendants of a URI. Matchers keep an index of children instead,
+    /// see Matcher.subtree.
+    pub fn descendants(&self, uri: &str) -> HashSet<String> {
+        fuzon::ChildIndex::new(&self.0)
+            .descendants(uri)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Ancestors following the first parent at each step, nearest first.
+    pub fn lineage(&self, uri: &str) -> Vec<String> {
+        self.0.lineage(uri).into_iter().map(String::from).collect()
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Deprecated URIs along with their replacements, in the same form as Term.uri.
+/// This wraps fuzon::Deprecations.
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct Deprecations(fuzon::Deprecations);
+
+#[pymethods]
+impl Deprecations {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a URI as deprecated, optionally in favour of another one.
+    #[pyo3(signature = (uri, replacement=None))]
+    pub fn deprecate(&mut self, uri: &str, replacement: Option<&str>) {
+        match replacement {
+            Some(replacement) => self.0.add_replacement(uri, replacement),
+            None => self.0.deprecate(uri),
+        }
+    }
+
+    pub fn is_deprecated(&self, uri: &str) -> bool {
+        self.0.is_deprecated(uri)
+    }
+
+    /// Replacements of a deprecated URI.
+    pub fn replaced_by(&self, uri: &str) -> Vec<String> {
+        self.0.replaced_by(uri).to_vec()
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// rdf:type of URIs, in the same form as Term.uri.
+/// This wraps fuzon::TypeIndex.
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct TypeIndex(fuzon::TypeIndex);
+
+#[pymethods]
+impl TypeIndex {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that a URI has a type.
+    pub fn add_type(&mut self, uri: &str, rdf_type: &str) {
+        self.0.add_type(uri, rdf_type);
+    }
+
+    /// Types of a URI.
+    pub fn types(&self, uri: &str) -> Vec<String> {
+        self.0.types(uri).to_vec()
+    }
+
+    /// Kind of a URI (class, property or individual), if it is typed.
+    pub fn kind(&self, uri: &str) -> Option<String> {
+        self.0.kind(uri).map(|k| k.to_string())
+    }
+
+    /// Whether a URI matches any of the kinds (e.g. "class") or type IRIs.
+    /// Only OBO CURIEs are expanded, see Matcher.rank for declared prefixes.
+    pub fn matches(&self, uri: &str, types: Vec<String>) -> PyResult<bool> {
+        let prefixes = fuzon::PrefixMap::default();
+        let filters: Vec<_> = type_filters(types)?
+            .iter()
+            .map(|f| f.expand(&prefixes))
+            .collect();
+
+        Ok(self.0.matches(uri, &filters))
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.0.len()
+    }
+}
+
+// Parse kinds or type IRIs, raising ValueError for empty ones.
+fn type_filters(types: Vec<String>) -> PyResult<Vec<fuzon::TypeFilter>> {
+    types
+        .iter()
+        .map(|t| t.parse().map_err(PyValueError::new_err))
+        .collect()
+}
+
+/// Prefixes and their namespaces, used to compact URIs into CURIEs and expand
+/// CURIEs into URIs. This wraps fuzon::PrefixMap.
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct PrefixMap(fuzon::PrefixMap);
+
+#[pymethods]
+impl PrefixMap {
+    #[new]
+    #[pyo3(signature = (prefixes=None))]
+    pub fn new(prefixes: Option<HashMap<String, String>>) -> Self {
+        let mut map = fuzon::PrefixMap::new();
+        for (prefix, namespace) in prefixes.unwrap_or_default() {
+            map.insert(&prefix, &namespace);
+        }
+        PrefixMap(map)
+    }
+
+    /// Read a JSON-LD context, prefix map or bioregistry extended prefix map
+    /// from a file path or URL.
+    #[staticmethod]
+    pub fn from_file(path: &str) -> PyResult<Self> {
+        let reader = get_reader(path)?;
+        let map = fuzon::PrefixMap::from_reader(reader)
+            .map_err(|e| PyValueError::new_err(format!("{}: {}", path, e)))?;
+
+        Ok(PrefixMap(map))
+    }
+
+    /// Bind a prefix to a namespace.
+    pub fn insert(&mut self, prefix: &str, namespace: &str) {
+        self.0.insert(prefix, namespace)
+    }
+
+    /// Compact a URI into a CURIE, or return it as is if no prefix applies.
+    pub fn compact(&self, uri: &str) -> String {
+        self.0.compact(uri)
+    }
+
+    /// Expand a CURIE with a bound prefix into a URI.
+    pub fn expand(&self, curie: &str) -> Option<String> {
+        self.0.expand(curie)
+    }
+
+    /// URI of a term from an IRI or CURIE, expanded with the bound prefixes.
+    pub fn term_uri(&self, id: &str) -> String {
+        self.0.term_uri(id)
+    }
+
+    /// Prefixes and their namespaces.
+    pub fn prefixes(&self) -> HashMap<String, String> {
+        self.0
+            .iter()
+            .map(|(prefix, ns)| (prefix.to_string(), ns.to_string()))
+            .collect()
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// URI of a term from an IRI, with or without angle brackets, or an OBO CURIE.
+#[pyfunction]
+pub fn term_uri(id: &str) -> String {
+    fuzon::term_uri(id)
+}
+
+// Built-in scorer from its name, fzy by default.
+fn scorer_kind(scorer: Option<String>) -> PyResult<ScorerKind> {
+    scorer
+        .map(|name| name.parse().map_err(PyValueError::new_err))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+// Normalization from comma-separated steps, the default steps if None.
+fn normalizer(normalize: Option<String>) -> PyResult<Normalizer> {
+    normalize
+        .map(|steps| steps.parse().map_err(PyValueError::new_err))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Returns a vector of similarity scores for each term to the query.
+/// Terms are scored with fzy by default, or with the named scorer
+/// (jaro-winkler, levenshtein, damerau-levenshtein or token-set).
+/// The query and labels are compared as given, see Matcher.score to score
+/// labels normalized once, when building the matcher.
+#[pyfunction]
+#[pyo3(signature = (query, terms, scorer=None))]
+pub fn score_terms(query: String, terms: Vec<Term>, scorer: Option<String>) -> PyResult<Vec<f64>> {
+    let scorer = scorer_kind(scorer)?;
+    let scores: Vec<f64> = terms
+        .iter()
+        .map(|t| scorer.score(&query, &t.label).unwrap_or(0.0))
         .collect();
 
     Ok(scores)
 }
 
+// Search options from the keyword arguments of ranking methods.
+#[allow(clippy::too_many_arguments)]
+fn search_options(
+    lang: Option<String>,
+    scopes: Option<Vec<String>>,
+    roots: Option<Vec<String>>,
+    types: Option<Vec<String>>,
+    include_deprecated: bool,
+    fields: Option<HashMap<String, f64>>,
+    scorer: Option<String>,
+) -> PyResult<fuzon::SearchOptions> {
+    let scopes = scopes
+        .map(|scopes| {
+            scopes
+                .iter()
+                .map(|s| s.parse::<SynonymScope>().map_err(PyValueError::new_err))
+                .collect::<PyResult<Vec<_>>>()
+        })
+        .transpose()?;
+    let types = types.map(type_filters).transpose()?;
+    let fields = fields
+        .map(|weights| {
+            fuzon::FieldWeights::from_weights(weights.iter().map(|(f, w)| (f.as_str(), *w)))
+                .map_err(PyValueError::new_err)
+        })
+        .transpose()?;
+
+    Ok(fuzon::SearchOptions {
+        lang,
+        scopes,
+        roots,
+        types,
+        include_deprecated,
+        fields,
+        scorer: scorer_kind(scorer)?,
+        typos: None,
+    })
+}
+
+/// Terms indexed for matching, along with their definitions, hierarchy,
+/// types, deprecations and prefixes. This wraps fuzon::TermMatcher: labels
+/// are normalized and indexed once, when the matcher is built or loaded,
+/// and queries do not copy terms back and forth.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct Matcher(TermMatcher);
+
+#[pymethods]
+impl Matcher {
+    /// Index terms, described by their description, with optional hierarchy,
+    /// types, deprecations and prefixes. Labels are normalized with the
+    /// comma-separated steps of normalize, see score_terms.
+    #[new]
+    #[pyo3(signature = (terms, hierarchy=None, type_index=None, deprecations=None, prefixes=None, normalize=None))]
+    pub fn new(
+        terms: Vec<Term>,
+        hierarchy: Option<Hierarchy>,
+        type_index: Option<TypeIndex>,
+        deprecations: Option<Deprecations>,
+        prefixes: Option<PrefixMap>,
+        normalize: Option<String>,
+    ) -> PyResult<Self> {
+        let definitions = term_definitions(&terms);
+        let matcher = TermMatcher::from_terms(terms.into_iter().map(fuzon::Term::from).collect())
+            .with_normalizer(normalizer(normalize)?)
+            .with_definitions(definitions)
+            .with_prefixes(prefixes.unwrap_or_default().0)
+            .with_deprecations(deprecations.unwrap_or_default().0)
+            .with_hierarchy(hierarchy.unwrap_or_default().0)
+            .with_types(type_index.unwrap_or_default().0);
+
+        Ok(Matcher(matcher))
+    }
+
+    /// Index the terms of sources, gathered as in parse_sources, with labels
+    /// normalized with the comma-separated steps of normalize.
+    #[staticmethod]
+    #[pyo3(signature = (paths, add_predicates=None, remove_predicates=None, replace_predicates=None, lenient=false, format=None, table=None, synonyms=false, hierarchy=false, types=false, prefixes=None, definition_predicates=None, normalize=None))]
+    // Arguments map to Python keyword arguments
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_files(
+        paths: Vec<String>,
+        add_predicates: Option<Vec<String>>,
+        remove_predicates: Option<Vec<String>>,
+        replace_predicates: Option<Vec<String>>,
+        lenient: bool,
+        format: Option<String>,
+        table: Option<HashMap<String, String>>,
+        synonyms: bool,
+        hierarchy: bool,
+        types: bool,
+        prefixes: Option<PrefixMap>,
+        definition_predicates: Option<Vec<String>>,
+        normalize: Option<String>,
+    ) -> PyResult<Self> {
+        let matcher = builder(
+            add_predicates,
+            remove_predicates,
+            replace_predicates,
+            lenient,
+            format,
+            table,
+            synonyms,
+            hierarchy,
+            types,
+            prefixes,
+            definition_predicates,
+        )?
+        .with_normalizer(normalizer(normalize)?)
+        .build_from_paths(paths.iter().map(|p| p.as_str()).collect())?;
+
+        Ok(Matcher(matcher))
+    }
+
+    /// Load a serialized fuzon TermMatcher, along with its indices.
+    #[staticmethod]
+    pub fn load(path: PathBuf) -> PyResult<Self> {
+        Ok(Matcher(TermMatcher::load(&path)?))
+    }
+
+    /// Serialize the matcher as a fuzon TermMatcher, along with its
+    /// normalizer and normalized labels.
+    pub fn dump(&self, path: PathBuf) -> PyResult<()> {
+        self.0.dump(&path)?;
+
+        Ok(())
+    }
+
+    /// Terms of the matcher, described by the definition of their URI.
+    pub fn terms(&self) -> Vec<Term> {
+        self.0.terms.iter().map(|t| self.term(t, false)).collect()
+    }
+
+    pub fn hierarchy(&self) -> Hierarchy {
+        Hierarchy(self.0.hierarchy().clone())
+    }
+
+    pub fn type_index(&self) -> TypeIndex {
+        TypeIndex(self.0.types().clone())
+    }
+
+    pub fn deprecations(&self) -> Deprecations {
+        Deprecations(self.0.deprecations().clone())
+    }
+
+    pub fn prefixes(&self) -> PrefixMap {
+        PrefixMap(self.0.prefixes().clone())
+    }
+
+    /// Normalization steps of labels and queries, comma-separated.
+    pub fn normalize(&self) -> String {
+        self.0.normalizer().to_string()
+    }
+
+    /// Similarity score of each term to the query, 0 if its label does not
+    /// match. Terms are scored with fzy by default, or with the named scorer,
+    /// using the labels normalized when building the matcher.
+    #[pyo3(signature = (query, scorer=None))]
+    pub fn score(&self, query: &str, scorer: Option<String>) -> PyResult<Vec<f64>> {
+        let scorer = scorer_kind(scorer)?;
+        let scores = self
+            .0
+            .scores(query, &scorer)
+            .into_iter()
+            .map(|s| s.unwrap_or(0.0))
+            .collect();
+
+        Ok(scores)
+    }
+
+    /// Rank the n best terms (all if n is None) accepted by the filters which
+    /// match the query. Terms whose label does not match are left out.
+    /// Labels can be restricted to a language, and synonyms to some scopes.
+    /// Terms can be restricted to the subtrees of root URIs in the hierarchy,
+    /// and to some kinds or types in the type index. Deprecated terms are
+    /// excluded unless include_deprecated is set. Roots and types can be
+    /// CURIEs bound in the prefixes, which are also used to return CURIEs if
+    /// curie is set. With fields, URIs are ranked once by the weighted scores
+    /// of their labels, synonyms and definition, given as a dict, e.g.
+    /// {"definition": 2.0}, where missing fields keep their default weight
+    /// (label 1, synonyms 0.7, definition 0.5). Labels are scored with fzy by
+    /// default, or with the named scorer.
+    #[pyo3(signature = (query, n=None, lang=None, scopes=None, roots=None, types=None, include_deprecated=false, curie=false, fields=None, scorer=None))]
+    // Arguments map to Python keyword arguments
+    #[allow(clippy::too_many_arguments)]
+    pub fn rank(
+        &self,
+        query: &str,
+        n: Option<usize>,
+        lang: Option<String>,
+        scopes: Option<Vec<String>>,
+        roots: Option<Vec<String>>,
+        types: Option<Vec<String>>,
+        include_deprecated: bool,
+        curie: bool,
+        fields: Option<HashMap<String, f64>>,
+        scorer: Option<String>,
+    ) -> PyResult<Vec<Term>> {
+        let options = search_options(
+            lang,
+            scopes,
+            roots,
+            types,
+            include_deprecated,
+            fields,
+            scorer,
+        )?;
+        let terms = self
+            .0
+            .rank_top_terms(query, n.unwrap_or(usize::MAX), &options)
+            .into_iter()
+            .map(|(term, _)| self.term(term, curie))
+            .collect();
+
+        Ok(terms)
+    }
+
+    /// Rank the n best concepts (all if n is None), grouping terms by URI.
+    /// Concepts are ranked by their best matching label, or by their fields
+    /// if given. Filters and scorers are the same as in rank.
+    #[pyo3(signature = (query, n=None, lang=None, scopes=None, roots=None, types=None, include_deprecated=false, curie=false, fields=None, scorer=None))]
+    // Arguments map to Python keyword arguments
+    #[allow(clippy::too_many_arguments)]
+    pub fn rank_concepts(
+        &self,
+        query: &str,
+        n: Option<usize>,
+        lang: Option<String>,
+        scopes: Option<Vec<String>>,
+        roots: Option<Vec<String>>,
+        types: Option<Vec<String>>,
+        include_deprecated: bool,
+        curie: bool,
+        fields: Option<HashMap<String, f64>>,
+        scorer: Option<String>,
+    ) -> PyResult<Vec<Concept>> {
+        let options = search_options(
+            lang,
+            scopes,
+            roots,
+            types,
+            include_deprecated,
+            fields,
+            scorer,
+        )?;
+        let concepts = self
+            .0
+            .top_concepts_with(query, n.unwrap_or(usize::MAX), &options)
+            .into_iter()
+            .map(Concept::from)
+            .map(|mut concept| {
+                concept.description = self.0.definitions().get(&concept.uri).map(String::from);
+                if curie {
+                    concept.uri = self.0.compact(&concept.uri);
+                }
+                concept
+            })
+            .collect();
+
+        Ok(concepts)
+    }
+
+    /// Terms (labels) of a URI or CURIE, empty if it is unknown.
+    #[pyo3(signature = (id, curie=false))]
+    pub fn lookup(&self, id: &str, curie: bool) -> Vec<Term> {
+        self.0
+            .lookup_terms(id)
+            .into_iter()
+            .map(|t| self.term(t, curie))
+            .collect()
+    }
+
+    /// Compact a URI into a CURIE if a prefix applies, or return it as is.
+    pub fn compact(&self, uri: &str) -> String {
+        self.0.compact(uri)
+    }
+
+    /// URI of a term from an IRI or CURIE, expanded with the prefixes.
+    pub fn term_uri(&self, id: &str) -> String {
+        self.0.term_uri(id)
+    }
+
+    /// URIs of the direct parents of a URI.
+    pub fn parents(&self, uri: &str) -> Vec<String> {
+        self.0.hierarchy().parents(uri).to_vec()
+    }
+
+    /// URIs of all ancestors of a URI, nearest first.
+    pub fn ancestors(&self, uri: &str) -> Vec<String> {
+        self.0
+            .hierarchy()
+            .ancestors(uri)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// URIs replacing a deprecated URI.
+    pub fn replaced_by(&self, uri: &str) -> Vec<String> {
+        self.0.deprecations().replaced_by(uri).to_vec()
+    }
+
+    /// URIs of the roots (IRIs or CURIEs) and all their descendants.
+    pub fn subtree(&self, roots: Vec<String>) -> HashSet<String> {
+        let roots: Vec<String> = roots.iter().map(|r| self.0.term_uri(r)).collect();
+        self.0
+            .subtree(&roots)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.0.terms.len()
+    }
+}
+
+impl Matcher {
+    // Term described by the definition of its URI, compacted if curie is set.
+    fn term(&self, term: &fuzon::Term, curie: bool) -> Term {
+        Term {
+            uri: match curie {
+                true => self.0.compact(&term.uri),
+                false => term.uri.clone(),
+            },
+            description: self.0.definitions().get(&term.uri).map(String::from),
+            ..Term::from(term.clone())
+        }
+    }
+}
+
 /// Parse and filter RDF files to gather the union of all terms.
+/// Annotation predicates used for labels can be added to, removed from
+/// or replace the defaults, and synonyms of all scopes can be included.
+/// In lenient mode, invalid triples are skipped.
+/// The format is detected unless given as an extension or media type.
+/// Columns of tabular sources are given as a dict with keys id, label,
+/// synonyms, separator and uri_template.
 #[pyfunction]
-pub fn parse_files(paths: Vec<String>) -> PyResult<Vec<Term>> {
-    let readers = paths.iter().map(|p| get_source(p).unwrap()).collect();
-    let terms = gather_terms(readers)
-        .map(|t| Term::new(t.uri, t.label))
-        .collect();
+#[pyo3(signature = (paths, add_predicates=None, remove_predicates=None, replace_predicates=None, lenient=false, format=None, table=None, synonyms=false))]
+// Arguments map to Python keyword arguments
+#[allow(clippy::too_many_arguments)]
+pub fn parse_files(
+    paths: Vec<String>,
+    add_predicates: Option<Vec<String>>,
+    remove_predicates: Option<Vec<String>>,
+    replace_predicates: Option<Vec<String>>,
+    lenient: bool,
+    format: Option<String>,
+    table: Option<HashMap<String, String>>,
+    synonyms: bool,
+) -> PyResult<Vec<Term>> {
+    let (terms, _, _, _, _) = parse_sources(
+        paths,
+        add_predicates,
+        remove_predicates,
+        replace_predicates,
+        lenient,
+        format,
+        table,
+        synonyms,
+        false,
+        false,
+        None,
+        None,
+    )?;
 
     Ok(terms)
 }
 
-/// Extract terms from a serialized fuzon TermMatcher.
+/// Same as parse_files, but also returns deprecated terms and prefixes, and
+/// gathers the hierarchy and types of terms if requested. Input prefixes
+/// override those declared in sources. Terms are described by the definition
+/// of their URI, using the definition predicates if given.
+#[pyfunction]
+#[pyo3(signature = (paths, add_predicates=None, remove_predicates=None, replace_predicates=None, lenient=false, format=None, table=None, synonyms=false, hierarchy=false, types=false, prefixes=None, definition_predicates=None))]
+// Arguments map to Python keyword arguments
+#[allow(clippy::too_many_arguments)]
+pub fn parse_sources(
+    paths: Vec<String>,
+    add_predicates: Option<Vec<String>>,
+    remove_predicates: Option<Vec<String>>,
+    replace_predicates: Option<Vec<String>>,
+    lenient: bool,
+    format: Option<String>,
+    table: Option<HashMap<String, String>>,
+    synonyms: bool,
+    hierarchy: bool,
+    types: bool,
+    prefixes: Option<PrefixMap>,
+    definition_predicates: Option<Vec<String>>,
+) -> PyResult<(Vec<Term>, Hierarchy, TypeIndex, Deprecations, PrefixMap)> {
+    let matcher = builder(
+        add_predicates,
+        remove_predicates,
+        replace_predicates,
+        lenient,
+        format,
+        table,
+        synonyms,
+        hierarchy,
+        types,
+        prefixes,
+        definition_predicates,
+    )?
+    .build_from_paths(paths.iter().map(|p| p.as_str()).collect())?;
+    let hierarchy = Hierarchy(matcher.hierarchy().clone());
+    let type_index = TypeIndex(matcher.types().clone());
+    let deprecations = Deprecations(matcher.deprecations().clone());
+    let prefixes = PrefixMap(matcher.prefixes().clone());
+    let terms = matcher_terms(matcher);
+
+    Ok((terms, hierarchy, type_index, deprecations, prefixes))
+}
+
+// Builder gathering terms as configured by the keyword arguments of parse_sources.
+#[allow(clippy::too_many_arguments)]
+fn builder(
+    add_predicates: Option<Vec<String>>,
+    remove_predicates: Option<Vec<String>>,
+    replace_predicates: Option<Vec<String>>,
+    lenient: bool,
+    format: Option<String>,
+    table: Option<HashMap<String, String>>,
+    synonyms: bool,
+    hierarchy: bool,
+    types: bool,
+    prefixes: Option<PrefixMap>,
+    definition_predicates: Option<Vec<String>>,
+) -> PyResult<TermMatcherBuilder> {
+    let mut builder = TermMatcher::builder()
+        .lenient(lenient)
+        .hierarchy(hierarchy)
+        .types(types)
+        .with_prefixes(prefixes.unwrap_or_default().0);
+    if let Some(format) = format {
+        let source_format = parse_source_format(&format)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown format: {}", format)))?;
+        builder = builder.with_format(source_format);
+    }
+    if let Some(table) = table {
+        builder = builder.with_table_mapping(table_mapping(table)?);
+    }
+    if let Some(predicates) = definition_predicates {
+        builder = builder.with_definition_predicates(predicates);
+    }
+    if let Some(predicates) = replace_predicates {
+        builder = builder.with_predicates(predicates);
+    }
+    for predicate in add_predicates.unwrap_or_default() {
+        builder = builder.with_predicate(&predicate);
+    }
+    if synonyms {
+        builder = builder.with_synonyms();
+    }
+    for predicate in remove_predicates.unwrap_or_default() {
+        builder = builder.without_predicate(&predicate);
+    }
+
+    Ok(builder)
+}
+
+// Column mapping of tabular sources from a dict, starting from the defaults.
+fn table_mapping(table: HashMap<String, String>) -> PyResult<TableMapping> {
+    let mut mapping = TableMapping::default();
+    for (key, value) in table {
+        match key.as_str() {
+            "id" => mapping.id = value,
+            "label" => mapping.label = value,
+            "synonyms" => mapping.synonyms = Some(value),
+            "separator" => mapping.separator = value,
+            "uri_template" => mapping.uri_template = Some(value),
+            _ => return Err(PyValueError::new_err(format!("Unknown table key: {}", key))),
+        }
+    }
+
+    Ok(mapping)
+}
+
+/// Extract terms, along with their description, from a serialized fuzon TermMatcher.
 /// This is faster than parsing RDF files.
 #[pyfunction]
 pub fn load_terms(path: PathBuf) -> PyResult<Vec<Term>> {
-    let terms: Vec<Term> = TermMatcher::load(&path)?
-        .terms
-        .into_iter()
-        .map(|t| Term::new(t.uri, t.label))
-        .collect();
-
-    Ok(terms)
+    Ok(matcher_terms(TermMatcher::load(&path)?))
 }
 
-/// Serialize the provided terms as a fuzon TermMatcher.
+/// Serialize the provided terms with their description, and optionally their
+/// hierarchy, types, deprecations and prefixes, as a fuzon TermMatcher.
 #[pyfunction]
-pub fn dump_terms(terms: Vec<Term>, path: PathBuf) -> PyResult<()> {
-    let mut matcher = TermMatcher::new();
-    matcher.terms = terms
-        .into_iter()
-        .map(|t| fuzon::Term {
-            uri: t.uri,
-            label: t.label,
-        })
-        .collect();
+#[pyo3(signature = (terms, path, hierarchy=None, type_index=None, deprecations=None, prefixes=None))]
+pub fn dump_terms(
+    terms: Vec<Term>,
+    path: PathBuf,
+    hierarchy: Option<Hierarchy>,
+    type_index: Option<TypeIndex>,
+    deprecations: Option<Deprecations>,
+    prefixes: Option<PrefixMap>,
+) -> PyResult<()> {
+    let definitions = term_definitions(&terms);
+    let matcher = TermMatcher::from_terms(terms.into_iter().map(fuzon::Term::from).collect())
+        .with_definitions(definitions)
+        .with_prefixes(prefixes.unwrap_or_default().0)
+        .with_deprecations(deprecations.unwrap_or_default().0)
+        .with_hierarchy(hierarchy.unwrap_or_default().0)
+        .with_types(type_index.unwrap_or_default().0);
     matcher.dump(&path)?;
 
     Ok(())
@@ -96,7 +923,7 @@ pub fn dump_terms(terms: Vec<Term>, path: PathBuf) -> PyResult<()> {
 /// Get a full platform-specific cache path based on input collection of sources.
 #[pyfunction]
 pub fn get_cache_path(sources: Vec<String>) -> PyResult<String> {
-    let mut src_ref = sources.iter().map(|s| s.as_str()).collect();
+    let mut src_ref: Vec<&str> = sources.iter().map(|s| s.as_str()).collect();
     let cache_path = cache::get_cache_path(&mut src_ref)?;
 
     Ok(cache_path.to_str().unwrap().to_owned())
@@ -105,7 +932,7 @@ pub fn get_cache_path(sources: Vec<String>) -> PyResult<String> {
 /// Get a deterministic cache key based on input collection of sources
 #[pyfunction]
 pub fn get_cache_key(sources: Vec<String>) -> PyResult<String> {
-    let mut src_ref = sources.iter().map(|s| s.as_str()).collect();
+    let mut src_ref: Vec<&str> = sources.iter().map(|s| s.as_str()).collect();
 
     Ok(cache::get_cache_key(&mut src_ref)?)
 }
@@ -119,23 +946,20 @@ pub fn cache_by_source(sources: Vec<String>) -> PyResult<()> {
     Ok(())
 }
 
-/// Load terms from individual TermMatcher cache files for each source.
+/// Load and combine individual TermMatcher cache files for each source.
 #[pyfunction]
-pub fn load_by_source(sources: Vec<String>) -> PyResult<Vec<Term>> {
+pub fn load_by_source(sources: Vec<String>) -> PyResult<Matcher> {
     let src_ref = sources.iter().map(|s| s.as_str()).collect();
-    let terms = cache::load_by_source(src_ref)?
-        .terms
-        .into_iter()
-        .map(|t| Term::new(t.uri, t.label))
-        .collect();
 
-    Ok(terms)
+    Ok(Matcher(cache::load_by_source(src_ref)?))
 }
 
 #[pymodule]
 fn pyfuzon(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(score_terms, m)?)?;
+    m.add_function(wrap_pyfunction!(term_uri, m)?)?;
     m.add_function(wrap_pyfunction!(parse_files, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_sources, m)?)?;
     m.add_function(wrap_pyfunction!(load_terms, m)?)?;
     m.add_function(wrap_pyfunction!(dump_terms, m)?)?;
     m.add_function(wrap_pyfunction!(get_cache_key, m)?)?;
@@ -143,6 +967,12 @@ fn pyfuzon(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(cache_by_source, m)?)?;
     m.add_function(wrap_pyfunction!(load_by_source, m)?)?;
     m.add_class::<Term>()?;
+    m.add_class::<Concept>()?;
+    m.add_class::<Matcher>()?;
+    m.add_class::<Deprecations>()?;
+    m.add_class::<Hierarchy>()?;
+    m.add_class::<PrefixMap>()?;
+    m.add_class::<TypeIndex>()?;
 
     Ok(())
 }